@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span, spans_from_hits},
+    hittable_list::HittableList,
+    interval::Interval,
+    material::Material,
+    quad::Quad,
+    ray::Ray,
+    vector::{Point, Vector},
+};
+
+// An axis-aligned box between two opposite corners, made of six quads facing
+// outwards. Rotated boxes are made by placing one in an Instance.
+pub struct BoxShape {
+    sides: HittableList,
+}
+
+impl BoxShape {
+    pub fn new(a: Point, b: Point, material: Arc<Box<dyn Material + Sync + Send>>) -> Self {
+        let min = a.min(b);
+        let max = a.max(b);
+        let dx = Vector::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vector::new(0.0, max.y - min.y, 0.0);
+        let dz = Vector::new(0.0, 0.0, max.z - min.z);
+
+        let faces = [
+            (Point::new(min.x, min.y, max.z), dx, dy),  // front
+            (Point::new(max.x, min.y, max.z), -dz, dy), // right
+            (Point::new(max.x, min.y, min.z), -dx, dy), // back
+            (Point::new(min.x, min.y, min.z), dz, dy),  // left
+            (Point::new(min.x, max.y, max.z), dx, -dz), // top
+            (Point::new(min.x, min.y, min.z), dx, dz),  // bottom
+        ];
+        let mut sides = HittableList::new();
+        for (corner, u, v) in faces {
+            sides.add(Arc::new(Box::new(Quad::new(
+                corner,
+                u,
+                v,
+                material.clone(),
+            ))));
+        }
+        Self { sides }
+    }
+}
+
+impl Hittable for BoxShape {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.sides.hit(ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sides.bounding_box()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.sides.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        self.sides.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point) -> Vector {
+        self.sides.random(origin)
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        Some(spans_from_hits(self, ray))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    #[test]
+    fn every_face_points_outwards() {
+        // Corners in either order give the same box.
+        let boxed = BoxShape::new(
+            Point::new(1.0, 2.0, 3.0),
+            Point::new(0.0, 0.0, 0.0),
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)))),
+        );
+        let centre = Point::new(0.5, 1.0, 1.5);
+        let half_size = Vector::new(0.5, 1.0, 1.5);
+        for axis in 0..3 {
+            for side in [-1.0, 1.0] {
+                let mut outward = Vector::new(0.0, 0.0, 0.0);
+                outward[axis] = side;
+                let ray = Ray::new(centre + 10.0 * outward, -outward);
+                let rec = boxed
+                    .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                    .unwrap_or_else(|| panic!("missed the face towards {outward:?}"));
+                assert!((rec.t - (10.0 - half_size[axis])).abs() < 1e-12);
+                assert!(rec.front_face);
+                assert_eq!(rec.normal, outward);
+
+                // From inside the box the same face is seen from behind.
+                let ray = Ray::new(centre, outward);
+                let rec = boxed
+                    .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                    .unwrap();
+                assert!(!rec.front_face);
+                assert_eq!(rec.normal, -outward);
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    hittable_list::HittableList,
+    interval::Interval,
+    ray::Ray,
+};
+
+pub struct BvhNode {
+    left: Arc<Box<dyn Hittable>>,
+    right: Arc<Box<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        Self::build(list.objects().to_vec())
+    }
+
+    // Splits the objects in half along the longest axis of their bounding box,
+    // using the median centroid so both subtrees get the same number of objects.
+    fn build(mut objects: Vec<Arc<Box<dyn Hittable>>>) -> Self {
+        let bbox = objects.iter().fold(Aabb::EMPTY, |acc, object| {
+            Aabb::enclosing(&acc, &object.bounding_box())
+        });
+
+        let (left, right) = match objects.len() {
+            0 => {
+                let empty: Arc<Box<dyn Hittable>> = Arc::new(Box::new(HittableList::new()));
+                (empty.clone(), empty)
+            }
+            1 => (objects[0].clone(), objects[0].clone()),
+            2 => (objects[0].clone(), objects[1].clone()),
+            len => {
+                let axis = bbox.longest_axis();
+                let mid = len / 2;
+                objects.select_nth_unstable_by(mid, |a, b| {
+                    let a_centre = a.bounding_box().axis_interval(axis);
+                    let b_centre = b.bounding_box().axis_interval(axis);
+                    (a_centre.min + a_centre.max).total_cmp(&(b_centre.min + b_centre.max))
+                });
+                let right_objects = objects.split_off(mid);
+                (
+                    Arc::new(Box::new(Self::build(objects)) as Box<dyn Hittable>),
+                    Arc::new(Box::new(Self::build(right_objects)) as Box<dyn Hittable>),
+                )
+            }
+        };
+
+        BvhNode { left, right, bbox }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, ray_t) {
+            return None;
+        }
+
+        let hit_left = self.left.hit(ray, ray_t);
+        let right_max = match &hit_left {
+            Some(rec) => rec.t,
+            None => ray_t.max,
+        };
+        let hit_right = self.right.hit(ray, Interval::new(ray_t.min, right_max));
+
+        hit_right.or(hit_left)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Colour,
+        material::{Lambertian, Material},
+        rng::{self, random_range},
+        sphere::Sphere,
+        vector::Point,
+    };
+
+    fn random_point(extent: f64) -> Point {
+        Point::new(
+            random_range(-extent..extent),
+            random_range(-extent..extent),
+            random_range(-extent..extent),
+        )
+    }
+
+    #[test]
+    fn finds_the_same_nearest_hit_as_a_list() {
+        rng::seed(3);
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        let mut list = HittableList::new();
+        let mut for_bvh = HittableList::new();
+        for _ in 0..200 {
+            let sphere: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Sphere::new(
+                random_point(10.0),
+                random_range(0.1..1.0),
+                material.clone(),
+            )));
+            list.add(sphere.clone());
+            for_bvh.add(sphere);
+        }
+        let bvh = BvhNode::new(for_bvh);
+
+        let mut hits = 0;
+        for _ in 0..1000 {
+            let ray = Ray::new(random_point(15.0), random_point(1.0));
+            let ray_t = Interval::new(0.001, f64::INFINITY);
+            match (list.hit(&ray, ray_t), bvh.hit(&ray, ray_t)) {
+                (Some(expected), Some(rec)) => {
+                    assert_eq!(rec.t, expected.t);
+                    assert_eq!(rec.normal, expected.normal);
+                    hits += 1;
+                }
+                (None, None) => {}
+                _ => panic!("the BVH and the list disagree about whether the ray hits"),
+            }
+        }
+        assert!(hits > 100, "only {hits} rays hit anything");
+    }
+}
@@ -1,21 +1,93 @@
 use std::{
     cmp::max,
-    f64::INFINITY,
-    sync::{Arc, Mutex},
+    sync::atomic::{AtomicUsize, Ordering},
     thread,
 };
 
-use image::{ImageBuffer, Rgb};
-
 use crate::{
-    Colour, get_colour_from_pixel, hittable::Hittable, interval::Interval, ray::Ray, vector::Vector,
+    Colour,
+    framebuffer::Framebuffer,
+    integrator::{Integrator, IntegratorKind},
+    ray::Ray,
+    rng,
+    vector::Vector,
+    world::World,
 };
-use rand::{Rng, random_range};
+
+// The parameters of Camera::new and of the integrator, kept separate so scene files
+// and command line options can fill them in before the camera is built.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraSettings {
+    pub aspect_ratio: f64,
+    pub image_width: i32,
+    pub samples_per_pixel: i32,
+    pub max_depth: i32,
+    pub vfov: f64,
+    pub lookfrom: Vector,
+    pub lookat: Vector,
+    pub defocus_angle: f64,
+    pub focus_dist: f64,
+    pub russian_roulette_depth: i32,
+    pub integrator: IntegratorKind,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl CameraSettings {
+    pub fn build(&self) -> Camera {
+        let mut camera = Camera::new(
+            self.aspect_ratio,
+            self.image_width,
+            self.samples_per_pixel,
+            self.vfov,
+            self.lookfrom,
+            self.lookat,
+            self.defocus_angle,
+            self.focus_dist,
+        );
+        camera.set_shutter(self.shutter_open, self.shutter_close);
+        camera
+    }
+
+    pub fn integrator(&self) -> Box<dyn Integrator> {
+        self.integrator
+            .build(self.max_depth, self.russian_roulette_depth)
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            aspect_ratio: 16.0 / 9.0,
+            image_width: 400,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            vfov: 90.0,
+            lookfrom: Vector::new(0.0, 0.0, 0.0),
+            lookat: Vector::new(0.0, 0.0, -1.0),
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            russian_roulette_depth: 5,
+            integrator: IntegratorKind::Path,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+}
+
+// How far a render has got, reported as each line of the image is finished. Lines are
+// rendered in parallel, so they do not finish in order.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderProgress {
+    // The line just finished, counting from 0 at the top.
+    pub line: usize,
+    pub lines_done: usize,
+    pub lines: usize,
+}
 
 pub struct Camera {
     image_width: i32,
     samples_per_pixel: i32,
-    max_depth: i32,
     defocus_angle: f64,
     image_height: i32,
     pixel_samples_scale: f64,
@@ -25,14 +97,17 @@ pub struct Camera {
     pixel_delta_v: Vector,
     defocus_disk_u: Vector,
     defocus_disk_v: Vector,
+    seed: Option<u64>,
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         aspect_ratio: f64,
         image_width: i32,
         samples_per_pixel: i32,
-        max_depth: i32,
         vfov: f64,
         lookfrom: Vector,
         lookat: Vector,
@@ -74,7 +149,6 @@ impl Camera {
         Camera {
             image_width,
             samples_per_pixel,
-            max_depth,
             defocus_angle,
             image_height,
             pixel_samples_scale,
@@ -84,67 +158,89 @@ impl Camera {
             pixel_delta_v,
             defocus_disk_u,
             defocus_disk_v,
+            seed: None,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn render(&self, world: Arc<Box<dyn Hittable + Sync + Send>>, num_threads: i32) {
-        let mut pixels = vec![
-            vec![Colour::new(0.0, 0.0, 0.0); self.image_width as usize];
-            self.image_height as usize
-        ];
+    // Fixes the random numbers used by render, making the output reproducible
+    // regardless of the number of threads.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    // Each ray is cast at a random time between open and close, blurring objects that
+    // move during the exposure.
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter_open = open;
+        self.shutter_close = close;
+    }
 
-        let mut chunks = Vec::with_capacity(num_threads as usize);
-        let total_done = Arc::new(Mutex::new(0));
+    // Renders the world into a framebuffer of linear colours, splitting the lines of
+    // the image between num_threads threads.
+    pub fn render(
+        &self,
+        world: &World,
+        integrator: &dyn Integrator,
+        num_threads: usize,
+    ) -> Framebuffer {
+        self.render_with_progress(world, integrator, num_threads, |_| {})
+    }
+
+    // The same as render, but calls progress from the rendering threads as each line
+    // of the image is finished.
+    pub fn render_with_progress(
+        &self,
+        world: &World,
+        integrator: &dyn Integrator,
+        num_threads: usize,
+        progress: impl Fn(RenderProgress) + Sync,
+    ) -> Framebuffer {
+        let width = self.image_width as usize;
+        let mut framebuffer = Framebuffer::new(width, self.image_height as usize);
+        let seed = self.seed.unwrap_or_else(rng::random);
+
+        let lines_done = AtomicUsize::new(0);
+        let (lines_done, progress) = (&lines_done, &progress);
         thread::scope(|s| {
-            let chunk_size = (self.image_height as f32 / num_threads as f32).ceil() as i32;
-            for chunk in pixels.chunks_mut(chunk_size as usize) {
-                chunks.push(Arc::new(Mutex::new(chunk)));
-            }
-            for cur_thread in 0..num_threads {
-                let shared_world = world.clone();
-                let cur_chunk = chunks[cur_thread as usize].clone();
-                let cur_total = total_done.clone();
+            let chunk_size = (self.image_height as f32 / num_threads.max(1) as f32).ceil() as i32;
+            for (cur_thread, chunk) in framebuffer
+                .pixels_mut()
+                .chunks_mut(chunk_size as usize * width)
+                .enumerate()
+            {
+                let cur_thread = cur_thread as i32;
                 s.spawn(move || {
-                    let mut chunk = cur_chunk.lock().unwrap();
-                    for j in
-                        (cur_thread * chunk_size)..(cur_thread * chunk_size + chunk.len() as i32)
-                    {
-                        for i in 0..self.image_width {
+                    let first_line = cur_thread * chunk_size;
+                    for (line, row) in chunk.chunks_mut(width).enumerate() {
+                        let j = first_line + line as i32;
+                        // Each line gets its own sequence so the image does not depend on
+                        // which thread rendered it.
+                        rng::seed(seed ^ (j as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                        for (i, pixel) in row.iter_mut().enumerate() {
+                            let mut pixel_colour = Colour::new(0.0, 0.0, 0.0);
                             for _ in 0..self.samples_per_pixel {
-                                let cur_colour = self.ray_colour(
-                                    &self.get_ray(i, j),
-                                    shared_world.clone(),
-                                    self.max_depth,
-                                );
-                                chunk[(j % chunk_size) as usize][i as usize] += cur_colour;
+                                pixel_colour +=
+                                    integrator.radiance(self.get_ray(i as i32, j), world);
                             }
+                            *pixel = pixel_colour * self.pixel_samples_scale;
                         }
-                        let mut total = cur_total.lock().unwrap();
-                        *total += 1;
-                        println!(
-                            "Line {} Complete, {}/{} Total",
-                            j + 1,
-                            *total,
-                            self.image_height
-                        );
-                        drop(total);
+                        progress(RenderProgress {
+                            line: j as usize,
+                            lines_done: lines_done.fetch_add(1, Ordering::Relaxed) + 1,
+                            lines: self.image_height as usize,
+                        });
                     }
                 });
             }
         });
 
-        let mut img_buf = ImageBuffer::new(self.image_width as u32, self.image_height as u32);
-        for (x, y, pixel_out) in img_buf.enumerate_pixels_mut() {
-            let (r, g, b) =
-                get_colour_from_pixel(pixels[y as usize][x as usize] * self.pixel_samples_scale);
-            *pixel_out = Rgb([r, g, b]);
-        }
-        let _ = img_buf.save("render.png");
+        framebuffer
     }
 
     fn get_ray(&self, i: i32, j: i32) -> Ray {
-        let mut rng = rand::rng();
-        let offset = Vector::new(rng.random::<f64>() - 0.5, rng.random::<f64>() - 0.5, 0.0);
+        let offset = Vector::new(rng::random::<f64>() - 0.5, rng::random::<f64>() - 0.5, 0.0);
 
         let pixel = self.pixel00_loc
             + self.pixel_delta_u * (f64::from(i) + offset.x)
@@ -155,39 +251,12 @@ impl Camera {
             false => self.defocus_disk_sample(),
         };
         let direction = pixel - origin;
+        let time = match self.shutter_close > self.shutter_open {
+            true => rng::random_range(self.shutter_open..self.shutter_close),
+            false => self.shutter_open,
+        };
 
-        Ray::new(origin, direction)
-    }
-
-    fn ray_colour(
-        &self,
-        ray: &Ray,
-        world: Arc<Box<dyn Hittable + Sync + Send>>,
-        depth: i32,
-    ) -> Colour {
-        if depth <= 0 {
-            return Colour::new(0.0, 0.0, 0.0);
-        }
-
-        if let Some(rec) = world.hit(ray, Interval::new(0.001, INFINITY)) {
-            let ray_record = rec.material.scatter(&ray, &rec);
-            return match ray_record.ray {
-                Some(scattered) => ray_record.colour.mul_element_wise(self.ray_colour(
-                    &scattered,
-                    world,
-                    depth - 1,
-                )),
-                None => ray_record.colour,
-            };
-        }
-
-        // These lines create the normal bright background.
-        // let unit_direction = ray.direction.normalize();
-        // let a = 0.5 * (unit_direction.y + 1.0);
-        // Colour::new(1.0, 1.0, 1.0) * (1.0 - a) + Colour::new(0.5, 0.7, 1.0) * a
-
-        // Comment out the above lines and uncomment this line for a dark background.
-        0.25 * Colour::new(111.0 / 255.0, 144.0 / 255.0, 168.0 / 255.0)
+        Ray::with_time(origin, direction, time)
     }
 
     fn defocus_disk_sample(&self) -> Vector {
@@ -198,7 +267,11 @@ impl Camera {
 
 fn random_in_unit_disk() -> Vector {
     loop {
-        let p = Vector::new(random_range(-1.0..=1.0), random_range(-1.0..=1.0), 0.0);
+        let p = Vector::new(
+            rng::random_range(-1.0..=1.0),
+            rng::random_range(-1.0..=1.0),
+            0.0,
+        );
         if p.magnitude2() < 1.0 {
             return p;
         }
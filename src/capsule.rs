@@ -0,0 +1,180 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span, oriented_hit_record, spans_from_hits},
+    interval::Interval,
+    material::Material,
+    polynomial::solve_quadratic,
+    ray::Ray,
+    vector::{Onb, Point, Vector, dot},
+};
+
+// Every point within radius of the line segment from base to top: a cylinder with
+// a hemisphere on each end. u runs once around the axis and v from the bottom of
+// the lower hemisphere to the top of the upper one.
+pub struct Capsule {
+    base: Point,
+    height: f64,
+    radius: f64,
+    material: Arc<Box<dyn Material + Sync + Send>>,
+    // The axis runs along w.
+    onb: Onb,
+}
+
+impl Capsule {
+    pub fn new(
+        base: Point,
+        top: Point,
+        radius: f64,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        let axis = top - base;
+        let height = axis.magnitude();
+        Self {
+            base,
+            height,
+            radius,
+            material,
+            // With no length any axis will do, and the capsule is a sphere.
+            onb: Onb::new(match height > 0.0 {
+                true => axis,
+                false => Vector::new(0.0, 1.0, 0.0),
+            }),
+        }
+    }
+}
+
+impl Hittable for Capsule {
+    // Each part only counts where it lies beside its own stretch of the axis: the
+    // cylinder between the ends and each hemisphere beyond its end.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let origin = self.onb.to_local(ray.origin - self.base);
+        let direction = self.onb.to_local(ray.direction);
+        let radius_squared = self.radius * self.radius;
+
+        let mut ray_t = ray_t;
+        let mut closest = None;
+        // Keeps the hit if it is the nearest so far and its height along the axis
+        // falls within part.
+        let mut consider = |t: f64, part: Interval| {
+            let point = origin + t * direction;
+            if ray_t.surounds(t) && part.contains(point.z) {
+                closest = Some((t, point));
+                ray_t.max = t;
+            }
+        };
+
+        let a = direction.x * direction.x + direction.y * direction.y;
+        let b = 2.0 * (origin.x * direction.x + origin.y * direction.y);
+        let c = origin.x * origin.x + origin.y * origin.y - radius_squared;
+        for t in solve_quadratic(a, b, c) {
+            consider(t, Interval::new(0.0, self.height));
+        }
+
+        let ends = [
+            (0.0, Interval::new(f64::NEG_INFINITY, 0.0)),
+            (self.height, Interval::new(self.height, f64::INFINITY)),
+        ];
+        for (end, part) in ends {
+            let offset = origin - Vector::new(0.0, 0.0, end);
+            let a = direction.magnitude2();
+            let b = 2.0 * dot(offset, direction);
+            let c = offset.magnitude2() - radius_squared;
+            for t in solve_quadratic(a, b, c) {
+                consider(t, part);
+            }
+        }
+
+        let (t, point) = closest?;
+        let axis_point = Vector::new(0.0, 0.0, point.z.clamp(0.0, self.height));
+        let outward_normal = self.onb.to_world((point - axis_point) / self.radius);
+        let u = (point.y.atan2(point.x) + PI) / (2.0 * PI);
+        let v = (point.z + self.radius) / (self.height + 2.0 * self.radius);
+        Some(oriented_hit_record(
+            ray,
+            t,
+            outward_normal,
+            (u, v),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let top = self.base + self.height * self.onb.w;
+        let extent = Vector::new(self.radius, self.radius, self.radius);
+        Aabb::enclosing(
+            &Aabb::from_points(self.base - extent, self.base + extent),
+            &Aabb::from_points(top - extent, top + extent),
+        )
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        Some(spans_from_hits(self, ray))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    fn hit(origin: Point, direction: Vector) -> Option<HitRecord> {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        // Upright from the origin to (0, 2, 0), half a unit in radius.
+        let capsule = Capsule::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 2.0, 0.0),
+            0.5,
+            material,
+        );
+        capsule.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        )
+    }
+
+    fn assert_hit(rec: Option<HitRecord>, t: f64, outward_normal: Vector) {
+        let rec = rec.expect("ray should hit");
+        assert!((rec.t - t).abs() < 1e-9, "hit at {}, not {t}", rec.t);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, outward_normal);
+    }
+
+    #[test]
+    fn hemispheres_round_off_the_ends() {
+        let down = Vector::new(0.0, -1.0, 0.0);
+        assert_hit(
+            hit(Point::new(0.0, 5.0, 0.0), down),
+            2.5,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_hit(
+            hit(Point::new(0.0, -5.0, 0.0), -down),
+            4.5,
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        // 0.3 off the axis the top hemisphere is 0.4 above the end of the cylinder.
+        assert_hit(
+            hit(Point::new(0.3, 5.0, 0.0), down),
+            2.6,
+            Vector::new(0.6, 0.8, 0.0),
+        );
+        assert!(hit(Point::new(0.6, 5.0, 0.0), down).is_none());
+    }
+
+    #[test]
+    fn the_side_is_a_cylinder() {
+        let across = Vector::new(1.0, 0.0, 0.0);
+        assert_hit(
+            hit(Point::new(-5.0, 1.0, 0.0), across),
+            4.5,
+            Vector::new(-1.0, 0.0, 0.0),
+        );
+
+        let rec = hit(Point::new(0.0, 1.0, 0.0), across).expect("ray should leave the capsule");
+        assert!((rec.t - 0.5).abs() < 1e-9);
+        assert!(!rec.front_face);
+    }
+}
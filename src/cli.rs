@@ -0,0 +1,246 @@
+use std::{path::PathBuf, str::FromStr, thread};
+
+use ray_tracing::{camera::CameraSettings, integrator::IntegratorKind, vector::Vector};
+
+pub const USAGE: &str = "\
+Usage: ray-tracing [OPTIONS] [SCENE]
+
+Renders SCENE, a JSON scene file, or the built-in random spheres scene if none is given.
+
+Options:
+  -o, --output <FILE>        Image to write [default: render.png]
+  -w, --width <PIXELS>       Image width
+  -s, --spp <SAMPLES>        Samples per pixel
+  -d, --max-depth <BOUNCES>  Maximum number of bounces per path
+      --rr-depth <BOUNCES>   Bounces before paths may be ended by Russian roulette
+      --integrator <NAME>    path, direct or ambient_occlusion
+  -t, --threads <COUNT>      Render threads [default: available parallelism]
+      --seed <SEED>          Seed for reproducible renders
+  -q, --quiet                Do not print progress while rendering
+      --aspect-ratio <RATIO> Width divided by height
+      --vfov <DEGREES>       Vertical field of view
+      --lookfrom <X,Y,Z>     Camera position
+      --lookat <X,Y,Z>       Point the camera looks at
+      --defocus-angle <DEG>  Defocus blur cone angle, 0 for a pinhole camera
+      --focus-dist <DIST>    Distance to the plane in perfect focus
+  -h, --help                 Print this message";
+
+pub struct Options {
+    pub scene: Option<PathBuf>,
+    pub output: PathBuf,
+    pub threads: usize,
+    pub seed: Option<u64>,
+    pub quiet: bool,
+    pub width: Option<i32>,
+    pub samples_per_pixel: Option<i32>,
+    pub max_depth: Option<i32>,
+    pub russian_roulette_depth: Option<i32>,
+    pub integrator: Option<IntegratorKind>,
+    pub aspect_ratio: Option<f64>,
+    pub vfov: Option<f64>,
+    pub lookfrom: Option<Vector>,
+    pub lookat: Option<Vector>,
+    pub defocus_angle: Option<f64>,
+    pub focus_dist: Option<f64>,
+}
+
+impl Options {
+    // Returns None if the help text was asked for instead of a render.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+        let mut options = Options {
+            scene: None,
+            output: PathBuf::from("render.png"),
+            threads: thread::available_parallelism().map_or(1, |count| count.get()),
+            seed: None,
+            quiet: false,
+            width: None,
+            samples_per_pixel: None,
+            max_depth: None,
+            russian_roulette_depth: None,
+            integrator: None,
+            aspect_ratio: None,
+            vfov: None,
+            lookfrom: None,
+            lookat: None,
+            defocus_angle: None,
+            focus_dist: None,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Accept both "--flag value" and "--flag=value".
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{flag} needs a value"))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => options.output = PathBuf::from(value()?),
+                "-w" | "--width" => options.width = Some(positive(&flag, &value()?)?),
+                "-s" | "--spp" => options.samples_per_pixel = Some(positive(&flag, &value()?)?),
+                "-d" | "--max-depth" => options.max_depth = Some(positive(&flag, &value()?)?),
+                "--rr-depth" => {
+                    options.russian_roulette_depth = Some(non_negative(&flag, &value()?)?)
+                }
+                "--integrator" => options.integrator = Some(value()?.parse()?),
+                "-t" | "--threads" => options.threads = positive(&flag, &value()?)?,
+                "--seed" => options.seed = Some(parse(&flag, &value()?)?),
+                "-q" | "--quiet" => options.quiet = true,
+                "--aspect-ratio" => options.aspect_ratio = Some(positive(&flag, &value()?)?),
+                "--vfov" => options.vfov = Some(positive(&flag, &value()?)?),
+                "--lookfrom" => options.lookfrom = Some(vector(&flag, &value()?)?),
+                "--lookat" => options.lookat = Some(vector(&flag, &value()?)?),
+                "--defocus-angle" => options.defocus_angle = Some(parse(&flag, &value()?)?),
+                "--focus-dist" => options.focus_dist = Some(positive(&flag, &value()?)?),
+                other if other.starts_with('-') && other.len() > 1 => {
+                    return Err(format!("unknown option {other}"));
+                }
+                _ => match options.scene {
+                    Some(_) => return Err(format!("unexpected argument {arg}")),
+                    None => options.scene = Some(PathBuf::from(arg)),
+                },
+            }
+        }
+
+        Ok(Some(options))
+    }
+
+    // Overrides the scene's camera with anything given on the command line.
+    pub fn apply(&self, camera: &mut CameraSettings) {
+        if let Some(width) = self.width {
+            camera.image_width = width;
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            camera.samples_per_pixel = samples_per_pixel;
+        }
+        if let Some(max_depth) = self.max_depth {
+            camera.max_depth = max_depth;
+        }
+        if let Some(russian_roulette_depth) = self.russian_roulette_depth {
+            camera.russian_roulette_depth = russian_roulette_depth;
+        }
+        if let Some(integrator) = self.integrator {
+            camera.integrator = integrator;
+        }
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            camera.aspect_ratio = aspect_ratio;
+        }
+        if let Some(vfov) = self.vfov {
+            camera.vfov = vfov;
+        }
+        if let Some(lookfrom) = self.lookfrom {
+            camera.lookfrom = lookfrom;
+        }
+        if let Some(lookat) = self.lookat {
+            camera.lookat = lookat;
+        }
+        if let Some(defocus_angle) = self.defocus_angle {
+            camera.defocus_angle = defocus_angle;
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            camera.focus_dist = focus_dist;
+        }
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value \"{value}\" for {flag}"))
+}
+
+fn positive<T: FromStr + PartialOrd + Default>(flag: &str, value: &str) -> Result<T, String> {
+    match parse(flag, value)? {
+        parsed if parsed > T::default() => Ok(parsed),
+        _ => Err(format!("{flag} must be greater than zero, found {value}")),
+    }
+}
+
+fn non_negative<T: FromStr + PartialOrd + Default>(flag: &str, value: &str) -> Result<T, String> {
+    match parse(flag, value)? {
+        parsed if parsed >= T::default() => Ok(parsed),
+        _ => Err(format!("{flag} must not be negative, found {value}")),
+    }
+}
+
+fn vector(flag: &str, value: &str) -> Result<Vector, String> {
+    let components = value
+        .split(',')
+        .map(|component| parse(flag, component.trim()))
+        .collect::<Result<Vec<f64>, _>>()?;
+    match components[..] {
+        [x, y, z] => Ok(Vector::new(x, y, z)),
+        _ => Err(format!("{flag} expects X,Y,Z, found \"{value}\"")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Option<Options>, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_take_separate_or_inline_values() {
+        let options = parse_args(&[
+            "scene.json",
+            "-w",
+            "320",
+            "--spp=16",
+            "--rr-depth",
+            "0",
+            "--lookat",
+            "1,2,3",
+            "-q",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.scene, Some(PathBuf::from("scene.json")));
+        assert_eq!(options.width, Some(320));
+        assert_eq!(options.samples_per_pixel, Some(16));
+        assert_eq!(options.russian_roulette_depth, Some(0));
+        assert_eq!(options.lookat, Some(Vector::new(1.0, 2.0, 3.0)));
+        assert!(options.quiet);
+        assert_eq!(options.output, PathBuf::from("render.png"));
+    }
+
+    #[test]
+    fn help_asks_for_no_render() {
+        assert!(parse_args(&["-w", "10", "--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_arguments_are_reported() {
+        let error = |args: &[&str]| parse_args(args).err().unwrap();
+        assert_eq!(
+            error(&["--width", "0"]),
+            "--width must be greater than zero, found 0"
+        );
+        assert_eq!(
+            error(&["--rr-depth=-1"]),
+            "--rr-depth must not be negative, found -1"
+        );
+        assert_eq!(error(&["-s"]), "-s needs a value");
+        assert_eq!(
+            error(&["--seed", "abc"]),
+            "invalid value \"abc\" for --seed"
+        );
+        assert_eq!(
+            error(&["--lookfrom", "1,2"]),
+            "--lookfrom expects X,Y,Z, found \"1,2\""
+        );
+        assert_eq!(error(&["--frobnicate"]), "unknown option --frobnicate");
+        assert_eq!(error(&["a.json", "b.json"]), "unexpected argument b.json");
+    }
+}
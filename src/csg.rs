@@ -0,0 +1,219 @@
+use std::{str::FromStr, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span, pair_hits},
+    interval::Interval,
+    ray::Ray,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    // The first solid with the second cut out of it.
+    Difference,
+}
+
+impl CsgOperation {
+    // Whether a point is inside the combined solid, given whether it is inside each part.
+    fn contains(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_a || in_b,
+            CsgOperation::Intersection => in_a && in_b,
+            CsgOperation::Difference => in_a && !in_b,
+        }
+    }
+}
+
+impl FromStr for CsgOperation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "union" => Ok(CsgOperation::Union),
+            "intersection" => Ok(CsgOperation::Intersection),
+            "difference" => Ok(CsgOperation::Difference),
+            other => Err(format!(
+                "unknown operation \"{other}\", expected union, intersection or difference"
+            )),
+        }
+    }
+}
+
+// Two solids combined by a boolean operation. Both must enclose a volume, so that
+// their spans along a ray can be merged; an object without spans counts as empty.
+// Each surface keeps the material of the solid it came from, so a hole cut by a
+// difference is lined with the material of the solid that cut it.
+pub struct Csg {
+    a: Arc<Box<dyn Hittable>>,
+    b: Arc<Box<dyn Hittable>>,
+    operation: CsgOperation,
+    bbox: Aabb,
+}
+
+impl Csg {
+    pub fn new(
+        a: Arc<Box<dyn Hittable>>,
+        b: Arc<Box<dyn Hittable>>,
+        operation: CsgOperation,
+    ) -> Self {
+        let (a_box, b_box) = (a.bounding_box(), b.bounding_box());
+        let bbox = match operation {
+            CsgOperation::Union => Aabb::enclosing(&a_box, &b_box),
+            CsgOperation::Intersection => {
+                let overlap =
+                    |a: Interval, b: Interval| Interval::new(a.min.max(b.min), a.max.min(b.max));
+                Aabb::new(
+                    overlap(a_box.x, b_box.x),
+                    overlap(a_box.y, b_box.y),
+                    overlap(a_box.z, b_box.z),
+                )
+            }
+            CsgOperation::Difference => a_box,
+        };
+        Csg {
+            a,
+            b,
+            operation,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, ray_t) {
+            return None;
+        }
+        self.spans(ray)?
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .find(|rec| ray_t.surounds(rec.t))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Walks through the boundaries of both solids in order, keeping those where the
+    // ray crosses into or out of the combined solid. A kept boundary's normal already
+    // faces the ray, so only whether it is entered needs updating.
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        let boundaries = |object: &Arc<Box<dyn Hittable>>, from_a: bool| {
+            object
+                .spans(ray)
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(move |span| [(span.enter, from_a), (span.exit, from_a)])
+        };
+        let mut boundaries: Vec<(HitRecord, bool)> = boundaries(&self.a, true)
+            .chain(boundaries(&self.b, false))
+            .collect();
+        boundaries.sort_by(|(a, _), (b, _)| a.t.total_cmp(&b.t));
+
+        let (mut in_a, mut in_b) = (false, false);
+        let mut hits = Vec::new();
+        for (mut rec, from_a) in boundaries {
+            let was_inside = self.operation.contains(in_a, in_b);
+            match from_a {
+                true => in_a = rec.front_face,
+                false => in_b = rec.front_face,
+            }
+            let inside = self.operation.contains(in_a, in_b);
+            if inside != was_inside {
+                rec.front_face = inside;
+                hits.push(rec);
+            }
+        }
+        Some(pair_hits(hits.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Colour,
+        material::{Lambertian, Material},
+        sphere::Sphere,
+        vector::{Point, Vector},
+    };
+
+    // Unit spheres around the origin and (1, 0, 0), overlapping between x = 0 and 1.
+    fn csg(operation: CsgOperation) -> Csg {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        let sphere = |x: f64| -> Arc<Box<dyn Hittable>> {
+            Arc::new(Box::new(Sphere::new(
+                Point::new(x, 0.0, 0.0),
+                1.0,
+                material.clone(),
+            )))
+        };
+        Csg::new(sphere(0.0), sphere(1.0), operation)
+    }
+
+    // Where along the x axis each span enters and leaves, for a ray from x = -5.
+    fn span_ends(operation: CsgOperation) -> Vec<(f64, f64)> {
+        let ray = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        csg(operation)
+            .spans(&ray)
+            .unwrap()
+            .iter()
+            .map(|span| (span.enter.point.x, span.exit.point.x))
+            .collect()
+    }
+
+    fn hit_from(x: f64, direction: f64) -> Option<HitRecord> {
+        let ray = Ray::new(Point::new(x, 0.0, 0.0), Vector::new(direction, 0.0, 0.0));
+        csg(CsgOperation::Difference).hit(&ray, Interval::new(0.001, f64::INFINITY))
+    }
+
+    fn assert_spans(actual: Vec<(f64, f64)>, expected: &[(f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for ((enter, exit), (expected_enter, expected_exit)) in actual.iter().zip(expected) {
+            assert!((enter - expected_enter).abs() < 1e-9, "{actual:?}");
+            assert!((exit - expected_exit).abs() < 1e-9, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn spans_are_combined_by_the_operation() {
+        assert_spans(span_ends(CsgOperation::Union), &[(-1.0, 2.0)]);
+        assert_spans(span_ends(CsgOperation::Intersection), &[(0.0, 1.0)]);
+        assert_spans(span_ends(CsgOperation::Difference), &[(-1.0, 0.0)]);
+    }
+
+    #[test]
+    fn cut_surfaces_face_out_of_the_difference() {
+        let ray = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let spans = csg(CsgOperation::Difference).spans(&ray).unwrap();
+        assert!(spans[0].enter.front_face);
+        assert_eq!(spans[0].enter.normal, Vector::new(-1.0, 0.0, 0.0));
+        // Leaving through the surface of the second sphere, from its outside.
+        assert!(!spans[0].exit.front_face);
+        assert_eq!(spans[0].exit.normal, Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn rays_from_inside_find_where_they_leave() {
+        let rec = hit_from(-0.5, 1.0).expect("ray should leave the solid");
+        assert!((rec.t - 0.5).abs() < 1e-9);
+        assert!(!rec.front_face);
+
+        // From inside the hole, the solid is only behind the ray.
+        assert!(hit_from(0.5, 1.0).is_none());
+        let rec = hit_from(0.5, -1.0).expect("ray should reach the cut surface");
+        assert!((rec.t - 0.5).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vector::new(1.0, 0.0, 0.0));
+
+        let ray = Ray::new(Point::new(0.5, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let rec = csg(CsgOperation::Intersection)
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should leave the overlap");
+        assert!((rec.t - 0.5).abs() < 1e-9);
+        assert!(!rec.front_face);
+    }
+}
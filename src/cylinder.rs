@@ -0,0 +1,296 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    disk::disk_bounding_box,
+    hittable::{HitRecord, Hittable, Span, oriented_hit_record, spans_from_hits},
+    interval::Interval,
+    material::Material,
+    polynomial::solve_quadratic,
+    ray::Ray,
+    vector::{Onb, Point, Vector},
+};
+
+// A cone between base and top, with the given radius at each end, so that it is
+// pointed when one radius is zero and a cylinder when they are equal. The ends are
+// closed with flat caps unless they are turned off.
+//
+// On the side u runs once around the axis and v from base to top. On the caps they
+// are a flat projection of the cap onto the unit square.
+pub struct Cone {
+    base: Point,
+    height: f64,
+    base_radius: f64,
+    top_radius: f64,
+    caps: bool,
+    material: Arc<Box<dyn Material + Sync + Send>>,
+    // The axis runs along w.
+    onb: Onb,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point,
+        top: Point,
+        base_radius: f64,
+        top_radius: f64,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        let axis = top - base;
+        Self {
+            base,
+            height: axis.magnitude(),
+            base_radius,
+            top_radius,
+            caps: true,
+            material,
+            onb: Onb::new(axis),
+        }
+    }
+
+    pub fn with_caps(mut self, caps: bool) -> Self {
+        self.caps = caps;
+        self
+    }
+
+    // The surface is found in a frame with the base at the origin and the axis
+    // along z, then the normal is turned back into world space.
+    fn hit_local(&self, ray: &Ray, ray_t: Interval) -> Option<(f64, Vector, f64, f64)> {
+        let origin = self.onb.to_local(ray.origin - self.base);
+        let direction = self.onb.to_local(ray.direction);
+        let slope = (self.top_radius - self.base_radius) / self.height;
+        let radius_at = |z: f64| self.base_radius + slope * z;
+
+        let mut ray_t = ray_t;
+        let mut closest = None;
+
+        // The side satisfies x² + y² = radius_at(z)².
+        let radius = radius_at(origin.z);
+        let a = direction.x * direction.x + direction.y * direction.y
+            - slope * slope * direction.z * direction.z;
+        let b =
+            2.0 * (origin.x * direction.x + origin.y * direction.y - slope * direction.z * radius);
+        let c = origin.x * origin.x + origin.y * origin.y - radius * radius;
+        for t in solve_quadratic(a, b, c) {
+            let point = origin + t * direction;
+            if ray_t.surounds(t) && (0.0..=self.height).contains(&point.z) {
+                let normal = Vector::new(point.x, point.y, -slope * radius_at(point.z));
+                let u = (point.y.atan2(point.x) + PI) / (2.0 * PI);
+                closest = Some((t, normal, u, point.z / self.height));
+                ray_t.max = t;
+                break;
+            }
+        }
+
+        if self.caps && direction.z != 0.0 {
+            let cap_size = self.base_radius.max(self.top_radius);
+            let caps = [
+                (0.0, self.base_radius, -1.0),
+                (self.height, self.top_radius, 1.0),
+            ];
+            for (z, radius, side) in caps {
+                let t = (z - origin.z) / direction.z;
+                let point = origin + t * direction;
+                if ray_t.surounds(t) && point.x * point.x + point.y * point.y <= radius * radius {
+                    closest = Some((
+                        t,
+                        Vector::new(0.0, 0.0, side),
+                        0.5 * (point.x / cap_size + 1.0),
+                        0.5 * (point.y / cap_size + 1.0),
+                    ));
+                    ray_t.max = t;
+                }
+            }
+        }
+
+        closest.map(|(t, normal, u, v)| (t, self.onb.to_world(normal).normalize(), u, v))
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let (t, outward_normal, u, v) = self.hit_local(ray, ray_t)?;
+        Some(oriented_hit_record(
+            ray,
+            t,
+            outward_normal,
+            (u, v),
+            &self.material,
+        ))
+    }
+
+    // Encloses the disks at both ends.
+    fn bounding_box(&self) -> Aabb {
+        let top = self.base + self.height * self.onb.w;
+        Aabb::enclosing(
+            &disk_bounding_box(self.base, self.onb.w, self.base_radius),
+            &disk_bounding_box(top, self.onb.w, self.top_radius),
+        )
+    }
+
+    // Only closed cones are solid.
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        match self.caps {
+            true => Some(spans_from_hits(self, ray)),
+            false => None,
+        }
+    }
+}
+
+// A cylinder of the given radius between base and top, with flat caps unless they
+// are turned off. Textures wrap around it as on a cone.
+pub struct Cylinder {
+    cone: Cone,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point,
+        top: Point,
+        radius: f64,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        Self {
+            cone: Cone::new(base, top, radius, radius, material),
+        }
+    }
+
+    pub fn with_caps(mut self, caps: bool) -> Self {
+        self.cone = self.cone.with_caps(caps);
+        self
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.cone.hit(ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.cone.bounding_box()
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        self.cone.spans(ray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    fn grey() -> Arc<Box<dyn Material + Sync + Send>> {
+        Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))))
+    }
+
+    fn hit(object: &impl Hittable, origin: Point, direction: Vector) -> Option<HitRecord> {
+        object.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        )
+    }
+
+    fn assert_hit(rec: Option<HitRecord>, t: f64, outward_normal: Vector) {
+        let rec = rec.expect("ray should hit");
+        assert!((rec.t - t).abs() < 1e-9, "hit at {}, not {t}", rec.t);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, outward_normal);
+    }
+
+    // Upright with its base at the origin, two units tall and one in radius.
+    fn cylinder() -> Cylinder {
+        Cylinder::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 2.0, 0.0),
+            1.0,
+            grey(),
+        )
+    }
+
+    #[test]
+    fn cylinders_are_hit_on_the_side_and_caps() {
+        let cylinder = cylinder();
+        let side = hit(
+            &cylinder,
+            Point::new(-5.0, 1.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        assert_hit(side, 4.0, Vector::new(-1.0, 0.0, 0.0));
+
+        let top = hit(
+            &cylinder,
+            Point::new(0.3, 5.0, 0.2),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        assert_hit(top, 3.0, Vector::new(0.0, 1.0, 0.0));
+        let bottom = hit(
+            &cylinder,
+            Point::new(0.3, -5.0, 0.2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_hit(bottom, 5.0, Vector::new(0.0, -1.0, 0.0));
+
+        // Through the top cap before reaching the side it leaves by.
+        let slanted = hit(
+            &cylinder,
+            Point::new(-0.5, 3.0, 0.0),
+            Vector::new(1.0, -1.0, 0.0),
+        );
+        assert_hit(slanted, 1.0, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn open_cylinders_are_seen_through_the_ends() {
+        let cylinder = cylinder().with_caps(false);
+        let down = hit(
+            &cylinder,
+            Point::new(0.3, 5.0, 0.2),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        assert!(down.is_none());
+
+        // Seen from inside, the far side faces away from the ray.
+        let rec = hit(
+            &cylinder,
+            Point::new(-0.5, 3.0, 0.0),
+            Vector::new(1.0, -1.0, 0.0),
+        )
+        .expect("ray should hit the inside");
+        assert!((rec.t - 1.5).abs() < 1e-9);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn cones_are_hit_on_the_side_and_base() {
+        let cone = Cone::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 2.0, 0.0),
+            1.0,
+            0.0,
+            grey(),
+        );
+        // Halfway up the radius is 0.5, and the side slopes in by one unit for every two.
+        let side = hit(
+            &cone,
+            Point::new(-5.0, 1.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        assert_hit(side, 4.5, Vector::new(-2.0, 1.0, 0.0).normalize());
+
+        let base = hit(
+            &cone,
+            Point::new(0.2, -3.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_hit(base, 3.0, Vector::new(0.0, -1.0, 0.0));
+
+        let beside_the_tip = hit(
+            &cone,
+            Point::new(0.6, 5.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        assert_hit(beside_the_tip, 4.2, Vector::new(2.0, 1.0, 0.0).normalize());
+    }
+}
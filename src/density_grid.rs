@@ -0,0 +1,246 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    aabb::Aabb,
+    interval::Interval,
+    perlin::Perlin,
+    vector::{Point, Vector},
+};
+
+// The most voxels a grid may have along each axis and in total, which keeps grids to
+// at most a gigabyte.
+pub const MAX_RESOLUTION: usize = 2048;
+pub const MAX_VOXELS: usize = 1 << 27;
+
+// Densities stored at the centres of the voxels of a grid filling bounds, read in
+// between by trilinear interpolation. Densities are indexed with x changing fastest,
+// then y, then z. Outside the bounds the density is zero.
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    densities: Vec<f64>,
+    bounds: Aabb,
+    max_density: f64,
+}
+
+impl DensityGrid {
+    // Negative densities are taken as zero. Panics if the resolution is beyond the
+    // limits or there is not one density for every voxel.
+    pub fn new(resolution: [usize; 3], densities: Vec<f64>, bounds: Aabb) -> Self {
+        let count = voxel_count(resolution).expect("grid resolution out of range");
+        assert_eq!(densities.len(), count, "expected one density per voxel");
+        let densities: Vec<f64> = densities.into_iter().map(|d| d.max(0.0)).collect();
+        let max_density = densities.iter().copied().fold(0.0, f64::max);
+        DensityGrid {
+            resolution,
+            densities,
+            bounds,
+            max_density,
+        }
+    }
+
+    // A puffy cloud: turbulent Perlin noise, at the given frequency, eaten away more
+    // and more towards the edges of the bounds so that their shape does not show.
+    // Panics if the resolution is beyond the limits.
+    pub fn from_noise(resolution: [usize; 3], bounds: Aabb, scale: f64) -> Self {
+        let count = voxel_count(resolution).expect("grid resolution out of range");
+        let noise = Perlin::new();
+        let centre = bounds.centroid();
+        let half_size = Vector::new(bounds.x.size(), bounds.y.size(), bounds.z.size()) / 2.0;
+        let [nx, ny, nz] = resolution;
+
+        let mut densities = Vec::with_capacity(count);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let point = Point::new(
+                        voxel_centre(bounds.x, x, nx),
+                        voxel_centre(bounds.y, y, ny),
+                        voxel_centre(bounds.z, z, nz),
+                    );
+                    let offset = point - centre;
+                    let edge_distance = Vector::new(
+                        offset.x / half_size.x,
+                        offset.y / half_size.y,
+                        offset.z / half_size.z,
+                    )
+                    .magnitude();
+                    let turbulence = noise.turbulence(point * scale, 5);
+                    densities.push((turbulence - 0.4 * edge_distance * edge_distance).max(0.0));
+                }
+            }
+        }
+        DensityGrid::new(resolution, densities, bounds)
+    }
+
+    // A text voxel file: the resolution as three whole numbers, then a density for
+    // every voxel, all separated by whitespace.
+    pub fn load(path: impl AsRef<Path>, bounds: Aabb) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut words = text.split_whitespace();
+        let mut resolution = [0; 3];
+        for size in resolution.iter_mut() {
+            *size = words
+                .next()
+                .and_then(|word| word.parse().ok())
+                .filter(|&size| size > 0)
+                .ok_or_else(|| invalid_data("expected the resolution as three whole numbers"))?;
+        }
+        let densities = words
+            .map(|word| {
+                word.parse()
+                    .map_err(|_| invalid_data(&format!("invalid density \"{word}\"")))
+            })
+            .collect::<io::Result<Vec<f64>>>()?;
+        check_voxel_count(resolution, densities.len())?;
+        Ok(DensityGrid::new(resolution, densities, bounds))
+    }
+
+    // A raw voxel file: nothing but a little endian 32 bit float for every voxel, so
+    // the resolution must be given.
+    pub fn load_raw(
+        path: impl AsRef<Path>,
+        resolution: [usize; 3],
+        bounds: Aabb,
+    ) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        if bytes.len() % 4 != 0 {
+            return Err(invalid_data(
+                "the file size is not a whole number of floats",
+            ));
+        }
+        let densities: Vec<f64> = bytes
+            .chunks_exact(4)
+            .map(|chunk| f64::from(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])))
+            .collect();
+        check_voxel_count(resolution, densities.len())?;
+        Ok(DensityGrid::new(resolution, densities, bounds))
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    pub fn max_density(&self) -> f64 {
+        self.max_density
+    }
+
+    pub fn density(&self, point: Point) -> f64 {
+        if !(self.bounds.x.contains(point.x)
+            && self.bounds.y.contains(point.y)
+            && self.bounds.z.contains(point.z))
+        {
+            return 0.0;
+        }
+
+        // Each axis gives the two voxels either side of the point and how far it is
+        // between their centres.
+        let axis = |interval: Interval, coordinate: f64, size: usize| {
+            let position = (coordinate - interval.min) / interval.size() * size as f64 - 0.5;
+            let position = position.clamp(0.0, (size - 1) as f64);
+            let low = (position.floor() as usize).min(size - 1);
+            let high = (low + 1).min(size - 1);
+            (low, high, position - low as f64)
+        };
+        let [nx, ny, nz] = self.resolution;
+        let (x0, x1, fx) = axis(self.bounds.x, point.x, nx);
+        let (y0, y1, fy) = axis(self.bounds.y, point.y, ny);
+        let (z0, z1, fz) = axis(self.bounds.z, point.z, nz);
+        let at = |x: usize, y: usize, z: usize| self.densities[x + nx * (y + ny * z)];
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let along_x = |y: usize, z: usize| lerp(at(x0, y, z), at(x1, y, z), fx);
+        lerp(
+            lerp(along_x(y0, z0), along_x(y1, z0), fy),
+            lerp(along_x(y0, z1), along_x(y1, z1), fy),
+            fz,
+        )
+    }
+}
+
+// The number of voxels in a grid of the given resolution, or None if any side is
+// empty or beyond MAX_RESOLUTION, or there are more than MAX_VOXELS.
+pub fn voxel_count(resolution: [usize; 3]) -> Option<usize> {
+    if resolution
+        .iter()
+        .any(|&size| size == 0 || size > MAX_RESOLUTION)
+    {
+        return None;
+    }
+    resolution
+        .iter()
+        .try_fold(1usize, |count, &size| count.checked_mul(size))
+        .filter(|&count| count <= MAX_VOXELS)
+}
+
+fn voxel_centre(interval: Interval, index: usize, size: usize) -> f64 {
+    interval.min + (index as f64 + 0.5) / size as f64 * interval.size()
+}
+
+fn check_voxel_count(resolution: [usize; 3], count: usize) -> io::Result<()> {
+    let expected = voxel_count(resolution).ok_or_else(|| {
+        invalid_data(&format!(
+            "a {}x{}x{} grid is too large",
+            resolution[0], resolution[1], resolution[2]
+        ))
+    })?;
+    match count == expected {
+        true => Ok(()),
+        false => Err(invalid_data(&format!(
+            "expected {expected} densities for a {}x{}x{} grid, found {count}",
+            resolution[0], resolution[1], resolution[2]
+        ))),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_bounds() -> Aabb {
+        Aabb::from_points(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn voxel_centres_and_corners_keep_their_densities() {
+        let densities = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let grid = DensityGrid::new([2, 2, 2], densities.clone(), unit_bounds());
+        for (index, &expected) in densities.iter().enumerate() {
+            let [x, y, z] = [index & 1, (index >> 1) & 1, index >> 2];
+            let centre = Point::new(
+                0.25 + 0.5 * x as f64,
+                0.25 + 0.5 * y as f64,
+                0.25 + 0.5 * z as f64,
+            );
+            assert!((grid.density(centre) - expected).abs() < 1e-12);
+            // Beyond the outermost centres the density stays at theirs, out to the
+            // corners of the bounds.
+            let corner = Point::new(x as f64, y as f64, z as f64);
+            assert!((grid.density(corner) - expected).abs() < 1e-12);
+        }
+
+        // Halfway between centres is the average of the eight.
+        assert!((grid.density(Point::new(0.5, 0.5, 0.5)) - 4.5).abs() < 1e-12);
+        assert_eq!(grid.density(Point::new(1.5, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.max_density(), 8.0);
+    }
+
+    #[test]
+    fn resolutions_are_limited() {
+        assert_eq!(voxel_count([4, 5, 6]), Some(120));
+        assert_eq!(voxel_count([0, 5, 6]), None);
+        assert_eq!(voxel_count([MAX_RESOLUTION + 1, 1, 1]), None);
+        assert_eq!(
+            voxel_count([MAX_RESOLUTION, MAX_RESOLUTION, MAX_RESOLUTION]),
+            None
+        );
+        assert_eq!(voxel_count([usize::MAX, usize::MAX, 2]), None);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, area_to_solid_angle_pdf},
+    interval::Interval,
+    material::Material,
+    plane::intersect_plane,
+    ray::Ray,
+    rng::random,
+    vector::{Onb, Point, Vector, dot},
+};
+
+// A flat circle facing along normal. The texture coordinates are the angle around
+// the centre, as a fraction of a turn, and the distance from it as a fraction of
+// the radius.
+pub struct Disk {
+    pub centre: Point,
+    pub normal: Vector,
+    pub radius: f64,
+    pub material: Arc<Box<dyn Material + Sync + Send>>,
+    onb: Onb,
+}
+
+impl Disk {
+    pub fn new(
+        centre: Point,
+        normal: Vector,
+        radius: f64,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        let normal = normal.normalize();
+        Self {
+            centre,
+            normal,
+            radius,
+            material,
+            onb: Onb::new(normal),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let t = intersect_plane(ray, ray_t, self.centre, self.normal)?;
+        let point = ray.at(t);
+        let offset = point - self.centre;
+        if offset.magnitude2() > self.radius * self.radius {
+            return None;
+        }
+
+        let angle = dot(offset, self.onb.v).atan2(dot(offset, self.onb.u));
+        let front_face = dot(ray.direction, self.normal) < 0.0;
+        Some(HitRecord {
+            point,
+            normal: match front_face {
+                true => self.normal,
+                false => -self.normal,
+            },
+            t,
+            u: (angle + PI) / (2.0 * PI),
+            v: offset.magnitude() / self.radius,
+            front_face,
+            material: self.material.clone(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        disk_bounding_box(self.centre, self.normal, self.radius)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        match self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) {
+            Some(rec) => {
+                area_to_solid_angle_pdf(1.0 / (PI * self.radius * self.radius), &rec, direction)
+            }
+            None => 0.0,
+        }
+    }
+
+    // Uniformly distributed over the disk's area.
+    fn random(&self, origin: Point) -> Vector {
+        let r = self.radius * random::<f64>().sqrt();
+        let phi = 2.0 * PI * random::<f64>();
+        self.centre
+            + self
+                .onb
+                .to_world(Vector::new(r * phi.cos(), r * phi.sin(), 0.0))
+            - origin
+    }
+}
+
+// Along each axis a disk reaches out by its radius times the sine of the angle
+// between that axis and its normal.
+pub(crate) fn disk_bounding_box(centre: Point, normal: Vector, radius: f64) -> Aabb {
+    let extent = radius
+        * Vector::new(
+            (1.0 - normal.x * normal.x).max(0.0).sqrt(),
+            (1.0 - normal.y * normal.y).max(0.0).sqrt(),
+            (1.0 - normal.z * normal.z).max(0.0).sqrt(),
+        );
+    Aabb::from_points(centre - extent, centre + extent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    fn hit_from_above(x: f64, z: f64) -> Option<HitRecord> {
+        let disk = Disk::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 2.0, 0.0),
+            1.0,
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)))),
+        );
+        let ray = Ray::new(Point::new(x, 1.0, z), Vector::new(0.0, -1.0, 0.0));
+        disk.hit(&ray, Interval::new(0.001, f64::INFINITY))
+    }
+
+    #[test]
+    fn the_rim_is_part_of_the_disk() {
+        for (x, z) in [(1.0, 0.0), (0.0, -1.0), (0.707, 0.707)] {
+            let rec = hit_from_above(x, z).unwrap_or_else(|| panic!("missed ({x}, {z})"));
+            assert!((rec.v - (x * x + z * z).sqrt()).abs() < 1e-12);
+        }
+        for (x, z) in [(1.001, 0.0), (0.0, -1.001), (0.7072, 0.7072)] {
+            assert!(hit_from_above(x, z).is_none(), "hit ({x}, {z})");
+        }
+
+        let centre = hit_from_above(0.0, 0.0).unwrap();
+        assert!((centre.t - 1.0).abs() < 1e-12);
+        assert!(centre.front_face);
+        assert_eq!(centre.normal, Vector::new(0.0, 1.0, 0.0));
+    }
+}
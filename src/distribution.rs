@@ -0,0 +1,160 @@
+// A piecewise constant density over [0, 1), proportional to func, which is sampled by
+// inverting its cumulative distribution.
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i].abs() / n as f64;
+        }
+
+        let integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate().skip(1) {
+            *value = match integral > 0.0 {
+                true => *value / integral,
+                // Nothing to prefer, so fall back to uniform.
+                false => i as f64 / n as f64,
+            };
+        }
+
+        Distribution1D {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    // The average of func over [0, 1).
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    // Maps u in [0, 1) to a point in [0, 1), returning it with its density and the
+    // piece it falls in.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        let n = self.len();
+        let offset = (self.cdf.partition_point(|&value| value <= u).max(1) - 1).min(n - 1);
+
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = match width > 0.0 {
+            true => ((u - self.cdf[offset]) / width).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        let x = ((offset as f64 + du) / n as f64).min(1.0 - f64::EPSILON);
+        (x, self.piece_pdf(offset), offset)
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        let offset = ((x * self.len() as f64) as usize).min(self.len() - 1);
+        self.piece_pdf(offset)
+    }
+
+    fn piece_pdf(&self, offset: usize) -> f64 {
+        match self.integral > 0.0 {
+            true => self.func[offset].abs() / self.integral,
+            false => 1.0,
+        }
+    }
+}
+
+// A piecewise constant density over the unit square, from a grid of values stored
+// row by row. A row is chosen first from the marginal density of the rows, then a
+// column within it.
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f64], width: usize, height: usize) -> Self {
+        let rows: Vec<Distribution1D> = func
+            .chunks(width.max(1))
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(Distribution1D::integral).collect());
+        Distribution2D { rows, marginal }
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    // Returns (x, y) in the unit square, with y counting rows from the first, and its
+    // density.
+    pub fn sample(&self, u: f64, v: f64) -> ((f64, f64), f64) {
+        let (y, row_pdf, row) = self.marginal.sample(v);
+        let (x, column_pdf, _) = self.rows[row].sample(u);
+        ((x, y), row_pdf * column_pdf)
+    }
+
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        let row = ((y * self.marginal.len() as f64) as usize).min(self.marginal.len() - 1);
+        self.marginal.pdf(y) * self.rows[row].pdf(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_follow_the_function() {
+        let distribution = Distribution1D::new(vec![1.0, 3.0]);
+        assert_eq!(distribution.integral(), 2.0);
+
+        // A quarter of the probability is in the first half.
+        let (x, pdf, piece) = distribution.sample(0.1);
+        assert!((x - 0.2).abs() < 1e-12);
+        assert_eq!((pdf, piece), (0.5, 0));
+        let (x, pdf, piece) = distribution.sample(0.625);
+        assert!((x - 0.75).abs() < 1e-12);
+        assert_eq!((pdf, piece), (1.5, 1));
+        assert_eq!(distribution.pdf(0.75), 1.5);
+    }
+
+    #[test]
+    fn zero_functions_are_sampled_uniformly() {
+        let distribution = Distribution1D::new(vec![0.0; 4]);
+        let (x, pdf, piece) = distribution.sample(0.6);
+        assert!((x - 0.6).abs() < 1e-12);
+        assert_eq!((pdf, piece), (1.0, 2));
+    }
+
+    #[test]
+    fn two_dimensional_samples_match_their_density() {
+        // Rows of two: only the first and last cells have any weight.
+        let distribution = Distribution2D::new(&[1.0, 0.0, 0.0, 3.0], 2, 2);
+        assert_eq!(distribution.integral(), 1.0);
+        for i in 0..20 {
+            for j in 0..20 {
+                let (u, v) = ((i as f64 + 0.5) / 20.0, (j as f64 + 0.5) / 20.0);
+                let ((x, y), pdf) = distribution.sample(u, v);
+                assert!(
+                    (x < 0.5) == (y < 0.5),
+                    "sampled an empty cell at ({x}, {y})"
+                );
+                assert_eq!(pdf, distribution.pdf(x, y));
+                let expected = match y < 0.5 {
+                    true => 1.0,
+                    false => 3.0,
+                };
+                assert_eq!(pdf, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::{f64::consts::PI, path::Path};
+
+use image::ImageResult;
+
+use crate::{
+    Colour, distribution::Distribution2D, rng::random, texture::load_linear, vector::Vector,
+};
+
+// Light arriving from infinitely far away, seen by rays that miss every object.
+pub trait Environment: Sync + Send {
+    fn value(&self, direction: Vector) -> Colour;
+
+    // Whether directions can be drawn from random, so that the environment is sampled
+    // directly like the emissive objects.
+    fn is_sampled(&self) -> bool {
+        false
+    }
+
+    // The solid angle density of random producing direction.
+    fn pdf_value(&self, _direction: Vector) -> f64 {
+        0.0
+    }
+
+    fn random(&self) -> Vector {
+        Vector::new(1.0, 0.0, 0.0)
+    }
+}
+
+// The same colour in every direction.
+pub struct ConstantEnvironment {
+    colour: Colour,
+}
+
+impl ConstantEnvironment {
+    pub fn new(colour: Colour) -> Self {
+        ConstantEnvironment { colour }
+    }
+}
+
+impl Environment for ConstantEnvironment {
+    fn value(&self, _direction: Vector) -> Colour {
+        self.colour
+    }
+}
+
+// Blends from bottom straight down to top straight up, like a simple sky.
+pub struct GradientSky {
+    bottom: Colour,
+    top: Colour,
+}
+
+impl GradientSky {
+    pub fn new(bottom: Colour, top: Colour) -> Self {
+        GradientSky { bottom, top }
+    }
+}
+
+impl Default for GradientSky {
+    fn default() -> Self {
+        GradientSky::new(Colour::new(1.0, 1.0, 1.0), Colour::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Environment for GradientSky {
+    fn value(&self, direction: Vector) -> Colour {
+        let a = 0.5 * (direction.normalize().y + 1.0);
+        self.bottom * (1.0 - a) + self.top * a
+    }
+}
+
+// An equirectangular image wrapped around the scene, with +y up. High dynamic range
+// formats such as .hdr and .exr are used as they are, other images are assumed to
+// be gamma encoded. Directions are sampled in proportion to the brightness of the
+// image, so a small sun is found by shadow rays rather than by chance.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    // Linear colours, row by row from the top of the image.
+    texels: Vec<Colour>,
+    distribution: Distribution2D,
+    // Radians about the y axis.
+    rotation: f64,
+    intensity: f64,
+}
+
+impl EnvironmentMap {
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let (width, height, texels) = load_linear(path.as_ref())?;
+        let distribution = brightness_distribution(&texels, width, height);
+
+        Ok(EnvironmentMap {
+            width,
+            height,
+            texels,
+            distribution,
+            rotation: 0.0,
+            intensity: 1.0,
+        })
+    }
+
+    // Turns the image about the vertical axis.
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees.to_radians();
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    // Wraps around horizontally and clamps at the poles.
+    fn texel(&self, x: i64, y: i64) -> Colour {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    // Image coordinates in [0, 1], with v running from the bottom to the top.
+    fn direction_to_uv(&self, direction: Vector) -> (f64, f64) {
+        let direction = direction.normalize();
+        let (sin, cos) = self.rotation.sin_cos();
+        let x = direction.x * cos - direction.z * sin;
+        let z = direction.x * sin + direction.z * cos;
+
+        let theta = (-direction.y).clamp(-1.0, 1.0).acos();
+        let phi = (-z).atan2(x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    fn uv_to_direction(&self, u: f64, v: f64) -> Vector {
+        let theta = v * PI;
+        let phi = u * 2.0 * PI - PI;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let x = sin_theta * phi.cos();
+        let z = -sin_theta * phi.sin();
+
+        let (sin, cos) = self.rotation.sin_cos();
+        Vector::new(x * cos + z * sin, -cos_theta, z * cos - x * sin)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn value(&self, direction: Vector) -> Colour {
+        if self.texels.is_empty() {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+
+        let (u, v) = self.direction_to_uv(direction);
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x0 + 1, y0) * tx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - tx) + self.texel(x0 + 1, y0 + 1) * tx;
+        (top * (1.0 - ty) + bottom * ty) * self.intensity
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.distribution.integral() > 0.0
+    }
+
+    // The density over the image divided by the area of the sphere each part of the
+    // image covers, which shrinks towards the poles.
+    fn pdf_value(&self, direction: Vector) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if !self.is_sampled() || sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, 1.0 - v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn random(&self) -> Vector {
+        let ((u, row), _) = self.distribution.sample(random(), random());
+        self.uv_to_direction(u, 1.0 - row)
+    }
+}
+
+// Luminance weighted by the solid angle each row of texels covers.
+fn brightness_distribution(texels: &[Colour], width: usize, height: usize) -> Distribution2D {
+    let weights: Vec<f64> = texels
+        .iter()
+        .enumerate()
+        .map(|(i, colour)| {
+            let theta = PI * ((i / width) as f64 + 0.5) / height as f64;
+            let luminance = 0.2126 * colour.x + 0.7152 * colour.y + 0.0722 * colour.z;
+            luminance * theta.sin()
+        })
+        .collect();
+    Distribution2D::new(&weights, width, height)
+}
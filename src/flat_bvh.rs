@@ -0,0 +1,434 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    hittable_list::HittableList,
+    interval::Interval,
+    ray::Ray,
+    vector::Point,
+};
+
+// Below this depth nodes are split at the median instead of by the SAH. Halving the
+// primitives each time, with offsets that fit in a u32, ends the tree within 32 more
+// levels however unbalanced the SAH splits above were.
+const MAX_SAH_DEPTH: usize = 64;
+// Traversal uses a fixed size stack, so the builder never produces a deeper tree.
+const MAX_DEPTH: usize = MAX_SAH_DEPTH + 32;
+const SAH_BUCKETS: usize = 12;
+// Cost of visiting an interior node relative to a single primitive intersection.
+const TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BvhStats {
+    pub primitive_count: usize,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: usize,
+    pub max_leaf_size: usize,
+}
+
+impl BvhStats {
+    pub fn average_leaf_size(&self) -> f64 {
+        match self.leaf_count {
+            0 => 0.0,
+            leaves => self.primitive_count as f64 / leaves as f64,
+        }
+    }
+}
+
+impl Display for BvhStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} primitives, {} nodes, {} leaves, depth {}, leaf size {:.2} avg / {} max",
+            self.primitive_count,
+            self.node_count,
+            self.leaf_count,
+            self.max_depth,
+            self.average_leaf_size(),
+            self.max_leaf_size
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LinearNode {
+    bbox: Aabb,
+    // First primitive for a leaf, index of the second child for an interior node.
+    // The first child of an interior node always directly follows it.
+    offset: u32,
+    primitive_count: u16,
+    axis: u8,
+}
+
+struct PrimitiveInfo {
+    index: usize,
+    bbox: Aabb,
+    centroid: Point,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    count: usize,
+    bbox: Aabb,
+}
+
+// A bounding volume hierarchy built with the surface area heuristic and stored as a
+// flat array of nodes in depth-first order, traversed without recursion. Primitives
+// are hittable objects unless the owner supplies its own, such as a mesh's triangle
+// indices, along with how to bound and intersect them.
+pub struct FlatBvh<P = Arc<Box<dyn Hittable>>> {
+    nodes: Vec<LinearNode>,
+    primitives: Vec<P>,
+    stats: BvhStats,
+}
+
+impl FlatBvh {
+    pub fn new(list: HittableList) -> Self {
+        Self::with_max_leaf_size(list, 4)
+    }
+
+    pub fn with_max_leaf_size(list: HittableList, max_leaf_size: usize) -> Self {
+        Self::build(
+            list.objects().to_vec(),
+            |object| object.bounding_box(),
+            max_leaf_size,
+        )
+    }
+}
+
+impl<P: Clone> FlatBvh<P> {
+    pub(crate) fn build(
+        primitives: Vec<P>,
+        bounding_box: impl Fn(&P) -> Aabb,
+        max_leaf_size: usize,
+    ) -> Self {
+        let mut info: Vec<PrimitiveInfo> = primitives
+            .iter()
+            .enumerate()
+            .map(|(index, primitive)| {
+                let bbox = bounding_box(primitive);
+                PrimitiveInfo {
+                    index,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+
+        let mut builder = Builder {
+            primitives: &primitives,
+            max_leaf_size: max_leaf_size.clamp(1, u16::MAX as usize),
+            nodes: Vec::with_capacity(2 * primitives.len()),
+            ordered: Vec::with_capacity(primitives.len()),
+            stats: BvhStats {
+                primitive_count: primitives.len(),
+                ..Default::default()
+            },
+        };
+        if !info.is_empty() {
+            builder.build(&mut info, 1);
+        }
+
+        FlatBvh {
+            nodes: builder.nodes,
+            primitives: builder.ordered,
+            stats: builder.stats,
+        }
+    }
+}
+
+impl<P> FlatBvh<P> {
+    pub fn stats(&self) -> &BvhStats {
+        &self.stats
+    }
+
+    pub(crate) fn bounds(&self) -> Aabb {
+        match self.nodes.first() {
+            Some(root) => root.bbox,
+            None => Aabb::EMPTY,
+        }
+    }
+
+    // Finds the nearest hit, calling hit_primitive on each primitive in the leaves the
+    // ray passes through with the interval narrowed to the closest hit so far.
+    pub(crate) fn hit_with(
+        &self,
+        ray: &Ray,
+        ray_t: Interval,
+        hit_primitive: impl Fn(&P, Interval) -> Option<HitRecord>,
+    ) -> Option<HitRecord> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let dir_is_neg = [
+            ray.direction.x < 0.0,
+            ray.direction.y < 0.0,
+            ray.direction.z < 0.0,
+        ];
+        let mut closest_so_far = ray_t.max;
+        let mut rec = None;
+
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node.bbox.hit(ray, Interval::new(ray_t.min, closest_so_far)) {
+                if node.primitive_count > 0 {
+                    let start = node.offset as usize;
+                    let end = start + node.primitive_count as usize;
+                    for primitive in &self.primitives[start..end] {
+                        if let Some(temp_rec) =
+                            hit_primitive(primitive, Interval::new(ray_t.min, closest_so_far))
+                        {
+                            closest_so_far = temp_rec.t;
+                            rec = Some(temp_rec);
+                        }
+                    }
+                } else {
+                    // Visit the child nearer to the ray origin first to tighten closest_so_far sooner.
+                    let (near, far) = match dir_is_neg[node.axis as usize] {
+                        true => (node.offset as usize, current + 1),
+                        false => (current + 1, node.offset as usize),
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+
+        rec
+    }
+}
+
+struct Builder<'a, P> {
+    primitives: &'a [P],
+    max_leaf_size: usize,
+    nodes: Vec<LinearNode>,
+    ordered: Vec<P>,
+    stats: BvhStats,
+}
+
+impl<P: Clone> Builder<'_, P> {
+    fn build(&mut self, info: &mut [PrimitiveInfo], depth: usize) -> usize {
+        let node_index = self.nodes.len();
+        let bbox = info
+            .iter()
+            .fold(Aabb::EMPTY, |acc, prim| Aabb::enclosing(&acc, &prim.bbox));
+        self.stats.node_count += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+
+        let centroid_bounds = info.iter().fold(Aabb::EMPTY, |acc, prim| {
+            Aabb::enclosing(&acc, &Aabb::from_points(prim.centroid, prim.centroid))
+        });
+        let axis = centroid_bounds.longest_axis();
+        let axis_bounds = centroid_bounds.axis_interval(axis);
+
+        // All centroids coincide, so no split can separate the primitives.
+        let degenerate = axis_bounds.size() <= 0.0001;
+        if info.len() == 1 || (degenerate && info.len() <= u16::MAX as usize) {
+            return self.make_leaf(info, bbox);
+        }
+
+        let split = match depth >= MAX_SAH_DEPTH {
+            true => (info.len() > self.max_leaf_size).then(|| split_at_median(info, axis)),
+            false => self.split(info, bbox, axis, axis_bounds, degenerate),
+        };
+        let Some(mid) = split else {
+            return self.make_leaf(info, bbox);
+        };
+
+        self.nodes.push(LinearNode {
+            bbox,
+            offset: 0,
+            primitive_count: 0,
+            axis: axis as u8,
+        });
+        let (left, right) = info.split_at_mut(mid);
+        self.build(left, depth + 1);
+        let second_child = self.build(right, depth + 1);
+        self.nodes[node_index].offset = second_child as u32;
+        node_index
+    }
+
+    // Returns the partition point of info after reordering it around the chosen split,
+    // or None if keeping the primitives in a single leaf is cheaper.
+    fn split(
+        &self,
+        info: &mut [PrimitiveInfo],
+        bbox: Aabb,
+        axis: usize,
+        axis_bounds: Interval,
+        degenerate: bool,
+    ) -> Option<usize> {
+        let len = info.len();
+        if degenerate || len <= 2 {
+            return Some(split_at_median(info, axis));
+        }
+
+        let bucket_of = |centroid: Point| {
+            let offset = (centroid[axis] - axis_bounds.min) / axis_bounds.size();
+            ((offset * SAH_BUCKETS as f64) as usize).min(SAH_BUCKETS - 1)
+        };
+
+        let mut buckets = [Bucket {
+            count: 0,
+            bbox: Aabb::EMPTY,
+        }; SAH_BUCKETS];
+        for prim in info.iter() {
+            let bucket = &mut buckets[bucket_of(prim.centroid)];
+            bucket.count += 1;
+            bucket.bbox = Aabb::enclosing(&bucket.bbox, &prim.bbox);
+        }
+
+        // Sweep from both ends so each candidate split is costed in linear time.
+        let mut below_area = [0.0; SAH_BUCKETS - 1];
+        let mut below_count = [0; SAH_BUCKETS - 1];
+        let mut running = Bucket {
+            count: 0,
+            bbox: Aabb::EMPTY,
+        };
+        for i in 0..SAH_BUCKETS - 1 {
+            running.count += buckets[i].count;
+            running.bbox = Aabb::enclosing(&running.bbox, &buckets[i].bbox);
+            below_count[i] = running.count;
+            below_area[i] = area_or_zero(&running);
+        }
+
+        let mut best_split = 0;
+        let mut best_cost = f64::INFINITY;
+        let mut running = Bucket {
+            count: 0,
+            bbox: Aabb::EMPTY,
+        };
+        for i in (1..SAH_BUCKETS).rev() {
+            running.count += buckets[i].count;
+            running.bbox = Aabb::enclosing(&running.bbox, &buckets[i].bbox);
+            let cost = below_count[i - 1] as f64 * below_area[i - 1]
+                + running.count as f64 * area_or_zero(&running);
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = i - 1;
+            }
+        }
+
+        let split_cost = TRAVERSAL_COST + best_cost / bbox.surface_area();
+        let leaf_cost = len as f64;
+        if len <= self.max_leaf_size && leaf_cost <= split_cost {
+            return None;
+        }
+
+        let mid = partition(info, |prim| bucket_of(prim.centroid) <= best_split);
+        match mid {
+            0 => None,
+            mid if mid == len => None,
+            mid => Some(mid),
+        }
+        .or_else(|| (len > self.max_leaf_size).then_some(len / 2))
+    }
+
+    fn make_leaf(&mut self, info: &[PrimitiveInfo], bbox: Aabb) -> usize {
+        let node_index = self.nodes.len();
+        self.nodes.push(LinearNode {
+            bbox,
+            offset: self.ordered.len() as u32,
+            // Every path to a leaf bounds its size by max_leaf_size or u16::MAX.
+            primitive_count: u16::try_from(info.len()).expect("too many primitives for a leaf"),
+            axis: 0,
+        });
+        self.ordered
+            .extend(info.iter().map(|prim| self.primitives[prim.index].clone()));
+        self.stats.leaf_count += 1;
+        self.stats.max_leaf_size = self.stats.max_leaf_size.max(info.len());
+        node_index
+    }
+}
+
+impl Hittable for FlatBvh {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.hit_with(ray, ray_t, |primitive, interval| {
+            primitive.hit(ray, interval)
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds()
+    }
+}
+
+fn area_or_zero(bucket: &Bucket) -> f64 {
+    match bucket.count {
+        0 => 0.0,
+        _ => bucket.bbox.surface_area(),
+    }
+}
+
+// Sorts info along axis and returns the index of its middle.
+fn split_at_median(info: &mut [PrimitiveInfo], axis: usize) -> usize {
+    info.sort_unstable_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+    info.len() / 2
+}
+
+fn partition<T>(slice: &mut [T], predicate: impl Fn(&T) -> bool) -> usize {
+    let mut first = 0;
+    for i in 0..slice.len() {
+        if predicate(&slice[i]) {
+            slice.swap(first, i);
+            first += 1;
+        }
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Colour,
+        material::{Lambertian, Material},
+        sphere::Sphere,
+        vector::Vector,
+    };
+
+    // Spheres spaced further and further apart make every SAH split peel off only
+    // the furthest few, so the tree passes the SAH depth limit.
+    #[test]
+    fn unbalanced_input_keeps_every_primitive() {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        let mut list = HittableList::new();
+        let centres: Vec<f64> = (0..200).map(|i| 2.0_f64.powi(i)).collect();
+        for &x in &centres {
+            list.add(Arc::new(Box::new(Sphere::new(
+                Point::new(x, 0.0, 0.0),
+                0.1 * x,
+                material.clone(),
+            ))));
+        }
+
+        let bvh = FlatBvh::with_max_leaf_size(list, 1);
+        assert!(bvh.stats().max_depth > MAX_SAH_DEPTH);
+        assert!(bvh.stats().max_depth <= MAX_DEPTH);
+        assert_eq!(bvh.stats().primitive_count, centres.len());
+
+        for &x in &centres {
+            let ray = Ray::new(Point::new(x, 10.0 * x, 0.0), Vector::new(0.0, -1.0, 0.0));
+            let rec = bvh
+                .hit(&ray, Interval::new(0.0, f64::INFINITY))
+                .unwrap_or_else(|| panic!("missed the sphere at {x}"));
+            assert!((rec.point.x - x).abs() <= 1e-9 * x);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{io::Cursor, path::Path};
+
+use image::{ImageFormat, ImageResult, Rgb, Rgb32FImage, RgbImage};
+
+use crate::{Colour, get_colour_from_pixel};
+
+// A rendered image of linear, unclamped colours, stored row by row from the top left.
+#[derive(Clone)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Colour>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Colour::new(0.0, 0.0, 0.0); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Colour] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Colour] {
+        &mut self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Colour {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, colour: Colour) {
+        self.pixels[y * self.width + x] = colour;
+    }
+
+    // Gamma corrects and quantises the image for display.
+    pub fn to_rgb8(&self) -> RgbImage {
+        RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let (r, g, b) = get_colour_from_pixel(self.get(x as usize, y as usize));
+            Rgb([r, g, b])
+        })
+    }
+
+    // Keeps the linear high dynamic range values, for formats such as .hdr and .exr.
+    pub fn to_rgb32f(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let colour = self.get(x as usize, y as usize);
+            Rgb([colour.x as f32, colour.y as f32, colour.z as f32])
+        })
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> ImageResult<Vec<u8>> {
+        let mut bytes = Cursor::new(Vec::new());
+        match is_hdr_format(format) {
+            true => self.to_rgb32f().write_to(&mut bytes, format)?,
+            false => self.to_rgb8().write_to(&mut bytes, format)?,
+        }
+        Ok(bytes.into_inner())
+    }
+
+    // The format is chosen from the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let path = path.as_ref();
+        match ImageFormat::from_path(path) {
+            Ok(format) if is_hdr_format(format) => self.to_rgb32f().save(path),
+            _ => self.to_rgb8().save(path),
+        }
+    }
+}
+
+fn is_hdr_format(format: ImageFormat) -> bool {
+    matches!(format, ImageFormat::Hdr | ImageFormat::OpenExr)
+}
@@ -1,15 +1,133 @@
 use std::sync::Arc;
 
-use crate::{interval::Interval, material::Material, ray::*, vector::*};
+use crate::{aabb::Aabb, interval::Interval, material::Material, ray::*, vector::*};
 
 pub struct HitRecord {
     pub point: Point,
     pub normal: Vector,
     pub t: f64,
+    // Surface coordinates of the hit, used to look up textures.
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
     pub material: Arc<Box<dyn Material + Sync + Send>>,
 }
 
 pub trait Hittable: Sync + Send {
     fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord>;
+
+    fn bounding_box(&self) -> Aabb;
+
+    // Whether the object emits light and should be sampled directly as a light, through
+    // random and pdf_value. Shapes that cannot pick points on themselves leave this
+    // false whatever their material: they still glow when a ray hits them, but are
+    // not aimed at by shadow rays.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // The solid angle density with which random(origin) picks direction. Only
+    // needs to be implemented by objects that can be used as lights.
+    fn pdf_value(&self, _origin: Point, _direction: Vector) -> f64 {
+        0.0
+    }
+
+    // A random, not necessarily normalised, direction from origin towards the object,
+    // or the zero vector if the object cannot be sampled, to go with a pdf_value of 0.
+    fn random(&self, _origin: Point) -> Vector {
+        Vector::new(0.0, 0.0, 0.0)
+    }
+
+    // The objects that a group, such as a list, is made of, so that the lights among
+    // them can be sampled one by one.
+    fn children(&self) -> Option<&[Arc<Box<dyn Hittable>>]> {
+        None
+    }
+
+    // Whether the object is a participating medium, which scatters light throughout
+    // its volume rather than at a surface.
+    fn is_medium(&self) -> bool {
+        false
+    }
+
+    // The fraction of the light travelling along the ray within ray_t that passes
+    // through the object without being scattered or absorbed. Only implemented by
+    // media, since surfaces block light by being hit.
+    fn transmittance(&self, _ray: &Ray, _ray_t: Interval) -> f64 {
+        1.0
+    }
+
+    // Every stretch of the ray's whole line, behind its origin as well as ahead, that
+    // lies inside the object, in order. Used to combine solids, so it is only
+    // implemented by objects that enclose a volume; others return None.
+    fn spans(&self, _ray: &Ray) -> Option<Vec<Span>> {
+        None
+    }
+}
+
+// Where a ray enters a solid and where it next leaves. As with every hit record the
+// normals face the ray, so front_face is set on enter and not on exit.
+pub struct Span {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
+// Finds the spans of a closed object by walking along the ray from hit to hit.
+pub(crate) fn spans_from_hits(object: &dyn Hittable, ray: &Ray) -> Vec<Span> {
+    let mut t = f64::NEG_INFINITY;
+    pair_hits(std::iter::from_fn(|| {
+        let rec = object.hit(ray, Interval::new(t, f64::INFINITY))?;
+        t = rec.t;
+        Some(rec)
+    }))
+}
+
+// Pairs each hit on the front of a surface with the next one on the back, given the
+// hits in order along a ray.
+pub(crate) fn pair_hits(hits: impl Iterator<Item = HitRecord>) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut enter: Option<HitRecord> = None;
+    for rec in hits {
+        match (rec.front_face, enter.take()) {
+            (true, _) => enter = Some(rec),
+            (false, Some(enter)) => spans.push(Span { enter, exit: rec }),
+            // A lone exit, from a ray grazing the surface.
+            (false, None) => {}
+        }
+    }
+    spans
+}
+
+// Converts a density over surface area at the hit into one over solid angle as seen
+// from the origin of the ray with the given direction.
+pub(crate) fn area_to_solid_angle_pdf(area_pdf: f64, rec: &HitRecord, direction: Vector) -> f64 {
+    let distance_squared = rec.t * rec.t * direction.magnitude2();
+    let cosine = dot(rec.normal, direction.normalize()).abs();
+    if cosine < 1e-12 {
+        return 0.0;
+    }
+    area_pdf * distance_squared / cosine
+}
+
+// Flips the outward normal to face the ray, as every hit record's normal does.
+pub(crate) fn oriented_hit_record(
+    ray: &Ray,
+    t: f64,
+    outward_normal: Vector,
+    (u, v): (f64, f64),
+    material: &Arc<Box<dyn Material + Sync + Send>>,
+) -> HitRecord {
+    let front_face = dot(ray.direction, outward_normal) < 0.0;
+    HitRecord {
+        point: ray.at(t),
+        normal: match front_face {
+            true => outward_normal,
+            false => -outward_normal,
+        },
+        t,
+        u,
+        v,
+        front_face,
+        material: material.clone(),
+    }
 }
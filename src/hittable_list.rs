@@ -1,25 +1,72 @@
 use std::sync::Arc;
 
 use crate::{
+    aabb::Aabb,
     hittable::{HitRecord, Hittable},
     interval::Interval,
     ray::Ray,
+    rng::random_range,
+    vector::{Point, Vector},
 };
 
 pub struct HittableList {
     objects: Vec<Arc<Box<dyn Hittable>>>,
+    // Where the emissive objects are in objects, which are the only ones sampled.
+    emissive: Vec<usize>,
+    bbox: Aabb,
 }
 
 impl HittableList {
     pub fn new() -> Self {
         Self {
             objects: Vec::<Arc<Box<dyn Hittable>>>::new(),
+            emissive: Vec::new(),
+            bbox: Aabb::EMPTY,
         }
     }
 
     pub fn add(&mut self, object: Arc<Box<dyn Hittable>>) {
+        self.bbox = Aabb::enclosing(&self.bbox, &object.bounding_box());
+        if object.is_emissive() {
+            self.emissive.push(self.objects.len());
+        }
         self.objects.push(object);
     }
+
+    pub fn objects(&self) -> &[Arc<Box<dyn Hittable>>] {
+        &self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    // The emissive objects in the list, to be sampled directly. Lists within it are
+    // opened up, so that every light is sampled as often as every other.
+    pub fn lights(&self) -> HittableList {
+        let mut lights = HittableList::new();
+        collect_lights(&self.objects, &mut lights);
+        lights
+    }
+}
+
+fn collect_lights(objects: &[Arc<Box<dyn Hittable>>], lights: &mut HittableList) {
+    for object in objects.iter().filter(|object| object.is_emissive()) {
+        match object.children() {
+            Some(children) => collect_lights(children, lights),
+            None => lights.add(object.clone()),
+        }
+    }
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Hittable for HittableList {
@@ -35,4 +82,144 @@ impl Hittable for HittableList {
         }
         rec
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        !self.emissive.is_empty()
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        self.objects
+            .iter()
+            .map(|object| object.transmittance(ray, ray_t))
+            .product()
+    }
+
+    // Picks between the emissive objects uniformly, so the density is the average of
+    // theirs.
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        if self.emissive.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .emissive
+            .iter()
+            .map(|&index| self.objects[index].pdf_value(origin, direction))
+            .sum();
+        sum / self.emissive.len() as f64
+    }
+
+    fn random(&self, origin: Point) -> Vector {
+        if self.emissive.is_empty() {
+            return Vector::new(0.0, 0.0, 0.0);
+        }
+        let index = self.emissive[random_range(0..self.emissive.len())];
+        self.objects[index].random(origin)
+    }
+
+    fn children(&self) -> Option<&[Arc<Box<dyn Hittable>>]> {
+        Some(&self.objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Colour,
+        environment::ConstantEnvironment,
+        material::{Lambertian, Light, Material},
+        sphere::Sphere,
+        world::World,
+    };
+
+    fn sphere(centre: Point, emissive: bool) -> Arc<Box<dyn Hittable>> {
+        let material: Arc<Box<dyn Material + Sync + Send>> = match emissive {
+            true => Arc::new(Box::new(Light::new(Colour::new(1.0, 1.0, 1.0)))),
+            false => Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)))),
+        };
+        Arc::new(Box::new(Sphere::new(centre, 1.0, material)))
+    }
+
+    #[test]
+    fn only_emissive_children_are_sampled() {
+        let light = sphere(Point::new(0.0, 0.0, -10.0), true);
+        let matte = sphere(Point::new(0.0, 0.0, 10.0), false);
+        let mut list = HittableList::new();
+        list.add(matte);
+        list.add(light.clone());
+
+        let origin = Point::new(0.0, 0.0, 0.0);
+        for _ in 0..100 {
+            assert!(list.random(origin).z < 0.0);
+        }
+        let towards_light = Vector::new(0.0, 0.0, -1.0);
+        assert_eq!(
+            list.pdf_value(origin, towards_light),
+            light.pdf_value(origin, towards_light)
+        );
+        assert_eq!(list.pdf_value(origin, Vector::new(0.0, 0.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn lights_in_nested_lists_are_opened_up() {
+        let mut inner = HittableList::new();
+        inner.add(sphere(Point::new(-5.0, 0.0, 0.0), true));
+        inner.add(sphere(Point::new(0.0, 5.0, 0.0), false));
+        inner.add(sphere(Point::new(0.0, -5.0, 0.0), true));
+        let mut outer = HittableList::new();
+        outer.add(Arc::new(Box::new(inner)));
+        outer.add(sphere(Point::new(5.0, 0.0, 0.0), true));
+        outer.add(sphere(Point::new(0.0, 0.0, 5.0), false));
+
+        let lights = outer.lights();
+        assert_eq!(lights.len(), 3);
+        assert!(
+            lights
+                .objects()
+                .iter()
+                .all(|light| light.children().is_none())
+        );
+    }
+
+    // Emissive, but leaves random and pdf_value to their defaults.
+    struct UnsampledLight(Arc<Box<dyn Hittable>>);
+
+    impl Hittable for UnsampledLight {
+        fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+            self.0.hit(ray, ray_t)
+        }
+
+        fn bounding_box(&self) -> Aabb {
+            self.0.bounding_box()
+        }
+
+        fn is_emissive(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn lights_that_cannot_be_sampled_give_no_direction() {
+        let mut list = HittableList::new();
+        list.add(Arc::new(Box::new(UnsampledLight(sphere(
+            Point::new(0.0, 0.0, -10.0),
+            true,
+        )))));
+        let world = World::new(list).with_environment(Arc::new(Box::new(
+            ConstantEnvironment::new(Colour::new(0.0, 0.0, 0.0)),
+        )));
+
+        let origin = Point::new(0.0, 0.0, 0.0);
+        assert_eq!(world.lights.random(origin), Vector::new(0.0, 0.0, 0.0));
+        assert_eq!(world.sample_light(origin), None);
+        assert_eq!(world.light_pdf(origin, Vector::new(0.0, 0.0, -1.0)), 0.0);
+        assert_eq!(
+            HittableList::new().random(origin),
+            Vector::new(0.0, 0.0, 0.0)
+        );
+    }
 }
@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span},
+    interval::Interval,
+    ray::Ray,
+    transform::Transform,
+    vector::{Point, Vector},
+};
+
+// A copy of a shared object placed in the scene by a transform. Rays are taken into
+// the object's own space to be intersected, so many instances can share one mesh and
+// its BVH.
+pub struct Instance {
+    object: Arc<Box<dyn Hittable>>,
+    transform: Transform,
+    bbox: Aabb,
+    // Light sampling densities are over solid angle, which only carries over from the
+    // object's space if the transform keeps angles.
+    samples_lights: bool,
+}
+
+impl Instance {
+    pub fn new(object: Arc<Box<dyn Hittable>>, transform: Transform) -> Self {
+        let bbox = transform.bounding_box(&object.bounding_box());
+        let samples_lights = object.is_emissive() && transform.preserves_angles();
+        Instance {
+            object,
+            transform,
+            bbox,
+            samples_lights,
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn to_local(&self, ray: &Ray) -> Ray {
+        to_local(&self.transform, ray)
+    }
+
+    fn to_world(&self, rec: HitRecord) -> HitRecord {
+        to_world(&self.transform, rec)
+    }
+}
+
+// Takes a ray into the space of an object placed by transform. The direction is not
+// normalised, so distances along the ray are the same in both spaces.
+pub(crate) fn to_local(transform: &Transform, ray: &Ray) -> Ray {
+    let inverse = transform.inverse();
+    Ray::with_time(
+        inverse.point(ray.origin),
+        inverse.vector(ray.direction),
+        ray.time,
+    )
+}
+
+// Brings a hit on an object placed by transform back out of its space.
+pub(crate) fn to_world(transform: &Transform, mut rec: HitRecord) -> HitRecord {
+    rec.point = transform.point(rec.point);
+    rec.normal = transform.normal(rec.normal).normalize();
+    rec
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let rec = self.object.hit(&self.to_local(ray), ray_t)?;
+        Some(self.to_world(rec))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.samples_lights
+    }
+
+    fn is_medium(&self) -> bool {
+        self.object.is_medium()
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        self.object.transmittance(&self.to_local(ray), ray_t)
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        let inverse = self.transform.inverse();
+        self.object
+            .pdf_value(inverse.point(origin), inverse.vector(direction))
+    }
+
+    fn random(&self, origin: Point) -> Vector {
+        let inverse = self.transform.inverse();
+        self.transform
+            .vector(self.object.random(inverse.point(origin)))
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        let spans = self.object.spans(&self.to_local(ray))?;
+        Some(
+            spans
+                .into_iter()
+                .map(|span| Span {
+                    enter: self.to_world(span.enter),
+                    exit: self.to_world(span.exit),
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Colour,
+        material::{Light, Material},
+        sphere::Sphere,
+    };
+
+    fn light() -> Arc<Box<dyn Material + Sync + Send>> {
+        Arc::new(Box::new(Light::new(Colour::new(1.0, 1.0, 1.0))))
+    }
+
+    fn unit_sphere() -> Arc<Box<dyn Hittable>> {
+        Arc::new(Box::new(Sphere::new(
+            Point::new(0.0, 0.0, 0.0),
+            1.0,
+            light(),
+        )))
+    }
+
+    #[test]
+    fn instances_hit_like_the_transformed_primitive() {
+        let transform = Transform::scale(Vector::new(2.0, 2.0, 2.0))
+            .then(Transform::rotate(Vector::new(0.0, 1.0, 0.0), 30.0))
+            .then(Transform::translate(Vector::new(3.0, 0.0, -1.0)));
+        let instance = Instance::new(unit_sphere(), transform);
+        let sphere = Sphere::new(Point::new(3.0, 0.0, -1.0), 2.0, light());
+        assert!(instance.is_emissive());
+
+        let rays = [
+            Ray::new(Point::new(-5.0, 0.5, -1.0), Vector::new(1.0, 0.0, 0.0)),
+            Ray::new(Point::new(3.0, 6.0, 0.0), Vector::new(0.1, -2.0, -0.3)),
+            Ray::new(Point::new(3.5, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for ray in rays {
+            let ray_t = Interval::new(0.001, f64::INFINITY);
+            let expected = sphere.hit(&ray, ray_t).expect("ray should hit the sphere");
+            let rec = instance
+                .hit(&ray, ray_t)
+                .expect("ray should hit the instance");
+            assert!((rec.t - expected.t).abs() < 1e-9);
+            assert_eq!(rec.point, expected.point);
+            assert_eq!(rec.normal, expected.normal);
+            assert_eq!(rec.front_face, expected.front_face);
+        }
+    }
+
+    #[test]
+    fn stretched_normals_stay_perpendicular_to_the_surface() {
+        // An ellipsoid x²/4 + y² + z² = 1, whose gradient gives the normal.
+        let instance = Instance::new(unit_sphere(), Transform::scale(Vector::new(2.0, 1.0, 1.0)));
+        assert!(!instance.is_emissive());
+
+        let ray = Ray::new(Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        let rec = instance
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should hit the ellipsoid");
+        let x = 3.0f64.sqrt();
+        assert!((rec.t - (5.0 - x)).abs() < 1e-9);
+        assert_eq!(rec.point, Point::new(x, 0.5, 0.0));
+        assert_eq!(rec.normal, Vector::new(x / 4.0, 0.5, 0.0).normalize());
+    }
+}
@@ -0,0 +1,385 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    Colour, hittable::HitRecord, interval::Interval, material::random_unit_vector, ray::Ray,
+    rng::random, vector::dot, world::World,
+};
+
+// Estimates the light arriving at the camera along a ray. The camera averages many
+// estimates per pixel, so each one only needs to be right on average.
+pub trait Integrator: Sync + Send {
+    fn radiance(&self, ray: Ray, world: &World) -> Colour;
+}
+
+// Which integrator to render with, as chosen in a scene file or on the command line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntegratorKind {
+    Path,
+    DirectLighting,
+    // Occluders further away than distance are ignored.
+    AmbientOcclusion { distance: f64 },
+}
+
+impl IntegratorKind {
+    pub fn build(self, max_depth: i32, russian_roulette_depth: i32) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Path => Box::new(
+                PathTracer::new(max_depth).with_russian_roulette_depth(russian_roulette_depth),
+            ),
+            IntegratorKind::DirectLighting => Box::new(DirectLighting::new(max_depth)),
+            IntegratorKind::AmbientOcclusion { distance } => {
+                Box::new(AmbientOcclusion::new(distance))
+            }
+        }
+    }
+}
+
+impl FromStr for IntegratorKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "path" => Ok(IntegratorKind::Path),
+            "direct" => Ok(IntegratorKind::DirectLighting),
+            "ambient_occlusion" | "ao" => Ok(IntegratorKind::AmbientOcclusion {
+                distance: f64::INFINITY,
+            }),
+            _ => Err(format!(
+                "unknown integrator \"{name}\", expected path, direct or ambient_occlusion"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for IntegratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegratorKind::Path => write!(f, "path"),
+            IntegratorKind::DirectLighting => write!(f, "direct"),
+            IntegratorKind::AmbientOcclusion { .. } => write!(f, "ambient_occlusion"),
+        }
+    }
+}
+
+// Full global illumination: follows each path bounce by bounce, sampling the lights
+// directly at every diffuse surface and weighting them against the material's own
+// sampling with multiple importance sampling.
+pub struct PathTracer {
+    max_depth: i32,
+    russian_roulette_depth: i32,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: i32) -> Self {
+        PathTracer {
+            max_depth,
+            russian_roulette_depth: 5,
+        }
+    }
+
+    // Paths are never ended early before this many bounces. max_depth remains a hard
+    // limit on the length of a path.
+    pub fn with_russian_roulette_depth(mut self, depth: i32) -> Self {
+        self.russian_roulette_depth = depth;
+        self
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, mut ray: Ray, world: &World) -> Colour {
+        let mut radiance = Colour::new(0.0, 0.0, 0.0);
+        // The fraction of the light arriving along the ray that reaches the camera.
+        let mut throughput = Colour::new(1.0, 1.0, 1.0);
+        // The density the ray was sampled with if it left a non-specular surface, in
+        // which case lights it hits were also sampled directly.
+        let mut scatter_pdf = None;
+
+        for bounce in 0..self.max_depth {
+            let Some(rec) = world.hit(&ray, Interval::new(0.001, f64::INFINITY)) else {
+                let environment = weighted_environment(&ray, world, scatter_pdf);
+                radiance += throughput.mul_element_wise(environment);
+                break;
+            };
+
+            let emitted = weighted_emission(&ray, &rec, world, scatter_pdf);
+            radiance += throughput.mul_element_wise(emitted);
+
+            let ray_record = rec.material.scatter(&ray, &rec);
+            let Some(scattered) = ray_record.ray else {
+                break;
+            };
+
+            if ray_record.pdf.is_some() {
+                radiance += throughput.mul_element_wise(sample_lights(&ray, &rec, world));
+            }
+            throughput = throughput.mul_element_wise(ray_record.colour);
+
+            // Russian roulette: past the minimum depth, paths that can only contribute a
+            // little are randomly ended, and the survivors weighted up to compensate.
+            if bounce >= self.russian_roulette_depth {
+                let survival = throughput.max_component().min(1.0);
+                if random::<f64>() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+
+            ray = scattered;
+            scatter_pdf = ray_record.pdf;
+        }
+
+        radiance
+    }
+}
+
+// Light that reaches the camera after at most one diffuse bounce. Mirrors and glass
+// are still followed, up to max_depth, so what they show is lit too.
+pub struct DirectLighting {
+    max_depth: i32,
+}
+
+impl DirectLighting {
+    pub fn new(max_depth: i32) -> Self {
+        DirectLighting { max_depth }
+    }
+}
+
+impl Integrator for DirectLighting {
+    fn radiance(&self, mut ray: Ray, world: &World) -> Colour {
+        let mut radiance = Colour::new(0.0, 0.0, 0.0);
+        let mut throughput = Colour::new(1.0, 1.0, 1.0);
+        let mut scatter_pdf = None;
+
+        for _ in 0..self.max_depth {
+            let Some(rec) = world.hit(&ray, Interval::new(0.001, f64::INFINITY)) else {
+                let environment = weighted_environment(&ray, world, scatter_pdf);
+                radiance += throughput.mul_element_wise(environment);
+                break;
+            };
+
+            let emitted = weighted_emission(&ray, &rec, world, scatter_pdf);
+            radiance += throughput.mul_element_wise(emitted);
+
+            // Having bounced off a diffuse surface, only the light this ray hits
+            // directly is counted.
+            if scatter_pdf.is_some() {
+                break;
+            }
+
+            let ray_record = rec.material.scatter(&ray, &rec);
+            let Some(scattered) = ray_record.ray else {
+                break;
+            };
+
+            if ray_record.pdf.is_some() {
+                radiance += throughput.mul_element_wise(sample_lights(&ray, &rec, world));
+            }
+            throughput = throughput.mul_element_wise(ray_record.colour);
+            ray = scattered;
+            scatter_pdf = ray_record.pdf;
+        }
+
+        radiance
+    }
+}
+
+// Shades each surface by how much of the hemisphere above it is open, ignoring
+// materials and lights entirely. Useful for checking geometry.
+pub struct AmbientOcclusion {
+    distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(distance: f64) -> Self {
+        AmbientOcclusion { distance }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: Ray, world: &World) -> Colour {
+        let white = Colour::new(1.0, 1.0, 1.0);
+        let Some(rec) = world.objects.hit(&ray, Interval::new(0.001, f64::INFINITY)) else {
+            return white;
+        };
+
+        // A cosine weighted direction, so the average visibility is the occlusion
+        // term without any further weighting.
+        let direction = rec.normal + random_unit_vector();
+        if dot(direction, rec.normal) <= 0.0 {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+
+        let occlusion_ray = Ray::with_time(rec.point, direction.normalize(), ray.time);
+        match world
+            .objects
+            .hit(&occlusion_ray, Interval::new(0.001, self.distance))
+        {
+            Some(_) => Colour::new(0.0, 0.0, 0.0),
+            None => white,
+        }
+    }
+}
+
+// Emission seen along ray. If the ray was sampled from a non-specular surface, the
+// light was also sampled directly from there, so the two are weighted by multiple
+// importance sampling.
+fn weighted_emission(
+    ray: &Ray,
+    rec: &HitRecord,
+    world: &World,
+    scatter_pdf: Option<f64>,
+) -> Colour {
+    let emitted = rec.material.emitted(rec);
+    match scatter_pdf {
+        Some(scatter_pdf) => {
+            let light_pdf = world.light_pdf(ray.origin, ray.direction);
+            emitted * power_heuristic(scatter_pdf, light_pdf)
+        }
+        None => emitted,
+    }
+}
+
+// The environment seen by a ray that escaped the scene, weighted like
+// weighted_emission if the environment is also sampled directly.
+fn weighted_environment(ray: &Ray, world: &World, scatter_pdf: Option<f64>) -> Colour {
+    let environment = world.environment.value(ray.direction);
+    match scatter_pdf {
+        Some(scatter_pdf) if world.environment.is_sampled() => {
+            let light_pdf = world.light_pdf(ray.origin, ray.direction);
+            environment * power_heuristic(scatter_pdf, light_pdf)
+        }
+        _ => environment,
+    }
+}
+
+// Next event estimation: light arriving at the hit along a direction chosen by
+// sampling the lights and environment, weighted against the material's own sampling.
+fn sample_lights(ray: &Ray, rec: &HitRecord, world: &World) -> Colour {
+    let black = Colour::new(0.0, 0.0, 0.0);
+    if !world.has_lights() {
+        return black;
+    }
+
+    let Some(direction) = world.sample_light(rec.point) else {
+        return black;
+    };
+    let light_pdf = world.light_pdf(rec.point, direction);
+    if light_pdf <= 0.0 {
+        return black;
+    }
+
+    let bsdf = rec.material.eval(ray, rec, direction);
+    if bsdf.x <= 0.0 && bsdf.y <= 0.0 && bsdf.z <= 0.0 {
+        return black;
+    }
+
+    // Whatever surface the shadow ray hits first is what is seen, so an occluder that
+    // is not itself a light contributes nothing. Media on the way dim it.
+    let shadow_ray = Ray::with_time(rec.point, direction, ray.time);
+    let (incoming, distance) = match world
+        .objects
+        .hit(&shadow_ray, Interval::new(0.001, f64::INFINITY))
+    {
+        Some(light_rec) => (light_rec.material.emitted(&light_rec), light_rec.t),
+        None if world.environment.is_sampled() => {
+            (world.environment.value(direction), f64::INFINITY)
+        }
+        None => (black, f64::INFINITY),
+    };
+    if incoming.x <= 0.0 && incoming.y <= 0.0 && incoming.z <= 0.0 {
+        return black;
+    }
+    let incoming = incoming * world.transmittance(&shadow_ray, Interval::new(0.001, distance));
+
+    let scatter_pdf = rec.material.scattering_pdf(ray, rec, direction);
+    let weight = power_heuristic(light_pdf, scatter_pdf);
+    incoming.mul_element_wise(bsdf) * (weight / light_pdf)
+}
+
+// Weight for a sample drawn with density pdf, when the same light could also have
+// been found by a strategy with density other_pdf.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    match a + b > 0.0 {
+        true => a / (a + b),
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        box_shape::BoxShape,
+        hittable::Hittable,
+        hittable_list::HittableList,
+        material::{Lambertian, Material},
+        plane::Plane,
+        rng,
+        vector::{Point, Vector},
+    };
+    use std::sync::Arc;
+
+    fn grey() -> Arc<Box<dyn Material + Sync + Send>> {
+        Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))))
+    }
+
+    fn world_of(object: impl Hittable + 'static) -> World {
+        let mut list = HittableList::new();
+        list.add(Arc::new(Box::new(object)));
+        World::new(list)
+    }
+
+    // Averaged over rays from origin along direction.
+    fn average_occlusion(
+        integrator: &AmbientOcclusion,
+        world: &World,
+        origin: Point,
+        direction: Vector,
+    ) -> f64 {
+        const SAMPLES: usize = 1000;
+        (0..SAMPLES)
+            .map(|_| integrator.radiance(Ray::new(origin, direction), world).x)
+            .sum::<f64>()
+            / SAMPLES as f64
+    }
+
+    #[test]
+    fn open_planes_are_unoccluded() {
+        rng::seed(7);
+        let world = world_of(Plane::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            grey(),
+        ));
+        let (origin, direction) = (Point::new(0.0, 1.0, 0.0), Vector::new(0.3, -1.0, 0.2));
+        let integrator = AmbientOcclusion::new(f64::INFINITY);
+        assert_eq!(
+            average_occlusion(&integrator, &world, origin, direction),
+            1.0
+        );
+    }
+
+    #[test]
+    fn closed_boxes_are_fully_occluded() {
+        rng::seed(8);
+        let world = world_of(BoxShape::new(
+            Point::new(-5.0, -5.0, -5.0),
+            Point::new(5.0, 5.0, 5.0),
+            grey(),
+        ));
+        let (origin, direction) = (Point::new(0.0, 0.0, 0.0), Vector::new(0.1, -1.0, 0.2));
+        let integrator = AmbientOcclusion::new(f64::INFINITY);
+        assert_eq!(
+            average_occlusion(&integrator, &world, origin, direction),
+            0.0
+        );
+
+        // The other walls are all further away than the occlusion distance.
+        let integrator = AmbientOcclusion::new(0.5);
+        assert_eq!(
+            average_occlusion(&integrator, &world, origin, direction),
+            1.0
+        );
+    }
+}
@@ -1,25 +1,32 @@
-// use std::f64::{INFINITY, NEG_INFINITY};
-
+#[derive(Clone, Copy, Debug)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
 }
 
-// const EMPTY: Interval = Interval {
-//     min: INFINITY,
-//     max: NEG_INFINITY,
-// };
+impl Interval {
+    pub const EMPTY: Interval = Interval {
+        min: f64::INFINITY,
+        max: f64::NEG_INFINITY,
+    };
 
-// const UNIVERSE: Interval = Interval {
-//     min: NEG_INFINITY,
-//     max: INFINITY,
-// };
+    pub const UNIVERSE: Interval = Interval {
+        min: f64::NEG_INFINITY,
+        max: f64::INFINITY,
+    };
 
-impl Interval {
     pub fn new(min: f64, max: f64) -> Self {
         Interval { min, max }
     }
 
+    // The smallest interval containing both a and b.
+    pub fn enclosing(a: Interval, b: Interval) -> Self {
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
     pub fn size(&self) -> f64 {
         self.max - self.min
     }
@@ -41,4 +48,12 @@ impl Interval {
             x
         }
     }
+
+    pub fn expand(&self, delta: f64) -> Self {
+        let padding = delta / 2.0;
+        Interval {
+            min: self.min - padding,
+            max: self.max + padding,
+        }
+    }
 }
@@ -1,14 +1,45 @@
 use crate::vector::Vector;
 use interval::Interval;
 
+pub mod aabb;
+pub mod box_shape;
+pub mod bvh;
 pub mod camera;
+pub mod capsule;
+pub mod csg;
+pub mod cylinder;
+pub mod density_grid;
+pub mod disk;
+pub mod distribution;
+pub mod environment;
+pub mod flat_bvh;
+pub mod framebuffer;
 pub mod hittable;
 pub mod hittable_list;
+pub mod instance;
+pub mod integrator;
 pub mod interval;
 pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod moving;
+pub mod obj;
+pub mod perlin;
+pub mod plane;
+pub mod polynomial;
+pub mod quad;
 pub mod ray;
+pub mod rng;
+pub mod scene;
+pub mod sdf;
+pub mod sky;
 pub mod sphere;
+pub mod texture;
+pub mod torus;
+pub mod transform;
+pub mod triangle;
 pub mod vector;
+pub mod world;
 
 pub type Colour = Vector;
 
@@ -19,6 +50,14 @@ fn linear_to_gamma(x: f64) -> f64 {
     }
 }
 
+// Inverse of linear_to_gamma, for colours read from images.
+pub fn gamma_to_linear(x: f64) -> f64 {
+    match x > 0.0 {
+        true => x * x,
+        false => 0.0,
+    }
+}
+
 pub fn get_colour_from_pixel(pixel: Colour) -> (u8, u8, u8) {
     let interval = Interval::new(0.0, 0.999);
 
@@ -1,30 +1,109 @@
+use std::env;
+use std::process;
 use std::sync::Arc;
 
-use rand::random;
+use cli::{Options, USAGE};
 use ray_tracing::Colour;
-use ray_tracing::camera::Camera;
+use ray_tracing::camera::{CameraSettings, RenderProgress};
+use ray_tracing::environment::ConstantEnvironment;
+use ray_tracing::flat_bvh::{BvhStats, FlatBvh};
 use ray_tracing::hittable::Hittable;
 use ray_tracing::hittable_list::*;
+use ray_tracing::integrator::IntegratorKind;
 use ray_tracing::material::Dielectric;
 use ray_tracing::material::Lambertian;
 use ray_tracing::material::Light;
 use ray_tracing::material::Material;
 use ray_tracing::material::Metal;
+use ray_tracing::plane::Plane;
+use ray_tracing::rng::{self, random};
+use ray_tracing::scene::{Scene, load_scene};
 use ray_tracing::sphere::*;
 use ray_tracing::vector::*;
+use ray_tracing::world::World;
+
+mod cli;
 
 fn main() {
-    let camera = Camera::new(
-        16.0 / 9.0,
-        1280,
-        500,
-        500,
-        20.0,
-        Vector::new(13.0, 2.0, 3.0),
-        Vector::new(0.0, 0.0, 0.0),
-        0.6,
-        10.0,
-    );
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    if let Some(seed) = options.seed {
+        rng::seed(seed);
+    }
+
+    let mut scene = match &options.scene {
+        Some(path) => match load_scene(path) {
+            Ok(scene) => scene,
+            Err(err) => {
+                eprintln!("error: {err}");
+                process::exit(1);
+            }
+        },
+        None => {
+            let (scene, stats) = random_spheres_scene();
+            if !options.quiet {
+                println!("BVH: {stats}");
+            }
+            scene
+        }
+    };
+    options.apply(&mut scene.camera);
+
+    let mut camera = scene.camera.build();
+    if let Some(seed) = options.seed {
+        camera.set_seed(seed);
+    }
+
+    let integrator = scene.camera.integrator();
+    let framebuffer = match options.quiet {
+        true => camera.render(&scene.world, integrator.as_ref(), options.threads),
+        false => camera.render_with_progress(
+            &scene.world,
+            integrator.as_ref(),
+            options.threads,
+            |progress: RenderProgress| {
+                println!(
+                    "Line {} Complete, {}/{} Total",
+                    progress.line + 1,
+                    progress.lines_done,
+                    progress.lines
+                );
+            },
+        ),
+    };
+    if let Err(err) = framebuffer.save(&options.output) {
+        eprintln!("error: could not save {}: {err}", options.output.display());
+        process::exit(1);
+    }
+}
+
+// Also returns the statistics of the BVH built over the spheres.
+fn random_spheres_scene() -> (Scene, BvhStats) {
+    let camera = CameraSettings {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 1280,
+        samples_per_pixel: 500,
+        max_depth: 500,
+        vfov: 20.0,
+        lookfrom: Vector::new(13.0, 2.0, 3.0),
+        lookat: Vector::new(0.0, 0.0, 0.0),
+        defocus_angle: 0.6,
+        focus_dist: 10.0,
+        russian_roulette_depth: 5,
+        integrator: IntegratorKind::Path,
+        shutter_open: 0.0,
+        shutter_close: 0.0,
+    };
 
     let mut world = HittableList::new();
 
@@ -78,15 +157,6 @@ fn main() {
     let material_right = make_material_shareable(Light::new(Colour::new(1.0, 0.9, 0.4)));
     let material_centre = make_material_shareable(Metal::new(Colour::new(0.7, 0.6, 0.5), 0.0));
 
-    //  The ground
-    world.add(create_world_object(
-        0.0,
-        -1000.0,
-        0.0,
-        999.99,
-        material_ground.clone(),
-    ));
-
     // Leftmost big ball in the render
     world.add(create_world_object(
         -4.0,
@@ -129,7 +199,31 @@ fn main() {
         material_right.clone(),
     ));
 
-    camera.render(Arc::new(Box::new(world)), 16);
+    let lights = world.lights();
+    let bvh = FlatBvh::new(world);
+    let stats = *bvh.stats();
+
+    // The ground is infinite, so it is tested alongside the BVH rather than in it
+    let mut objects = HittableList::new();
+    objects.add(Arc::new(Box::new(bvh)));
+    objects.add(Arc::new(Box::new(Plane::new(
+        Point::new(0.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        material_ground.clone(),
+    ))));
+
+    let scene = Scene {
+        camera,
+        world: World {
+            objects: Arc::new(Box::new(objects)),
+            lights,
+            media: HittableList::new(),
+            environment: Arc::new(Box::new(ConstantEnvironment::new(
+                0.25 * Colour::new(111.0 / 255.0, 144.0 / 255.0, 168.0 / 255.0),
+            ))),
+        },
+    };
+    (scene, stats)
 }
 
 fn make_material_shareable(
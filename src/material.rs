@@ -1,66 +1,255 @@
-use rand::{random, random_range};
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::{
     Colour,
     hittable::HitRecord,
     ray::Ray,
-    vector::{Vector, dot},
+    rng::{random, random_range},
+    texture::{SolidColour, Texture},
+    vector::{Onb, Vector, dot},
 };
 
 pub struct RayRecord {
     pub colour: Colour,
     pub ray: Option<Ray>,
+    // The solid angle density the ray was sampled with. None for specular scattering,
+    // whose single possible direction cannot be found by sampling lights.
+    pub pdf: Option<f64>,
 }
 
 impl RayRecord {
-    fn new(colour: Colour, ray: Option<Ray>) -> Self {
-        RayRecord { colour, ray }
+    pub fn new(colour: Colour, ray: Option<Ray>) -> Self {
+        RayRecord {
+            colour,
+            ray,
+            pdf: None,
+        }
+    }
+
+    pub fn sampled(colour: Colour, ray: Ray, pdf: f64) -> Self {
+        RayRecord {
+            colour,
+            ray: Some(ray),
+            pdf: Some(pdf),
+        }
     }
 }
 
 pub trait Material: Sync + Send {
+    // A record without a ray means the light was absorbed. The colour is the
+    // throughput of the scattered ray, already divided by its pdf.
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> RayRecord;
+
+    fn emitted(&self, _rec: &HitRecord) -> Colour {
+        Colour::new(0.0, 0.0, 0.0)
+    }
+
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // The BSDF times the cosine of the angle to the normal, for light arriving from
+    // direction. Used to weigh samples of the lights, so only non-specular materials
+    // need to implement it.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vector) -> Colour {
+        Colour::new(0.0, 0.0, 0.0)
+    }
+
+    // The density with which scatter would have picked direction.
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vector) -> f64 {
+        0.0
+    }
 }
 
 pub struct Lambertian {
-    albedo: Colour,
+    albedo: Arc<Box<dyn Texture>>,
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> RayRecord {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> RayRecord {
         let mut direction = rec.normal + random_unit_vector();
         if near_zero(direction) {
             direction = rec.normal;
         }
 
-        RayRecord::new(self.albedo, Some(Ray::new(rec.point, direction)))
+        RayRecord::sampled(
+            self.albedo.value(rec.u, rec.v, rec.point),
+            Ray::with_time(rec.point, direction, r_in.time),
+            self.scattering_pdf(r_in, rec, direction),
+        )
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: Vector) -> Colour {
+        let cosine = dot(rec.normal, direction.normalize()).max(0.0);
+        self.albedo.value(rec.u, rec.v, rec.point) * (cosine / PI)
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: Vector) -> f64 {
+        dot(rec.normal, direction.normalize()).max(0.0) / PI
     }
 }
 
 impl Lambertian {
     pub fn new(albedo: Colour) -> Self {
+        Lambertian::from_texture(Arc::new(Box::new(SolidColour::new(albedo))))
+    }
+
+    pub fn from_texture(albedo: Arc<Box<dyn Texture>>) -> Self {
         Lambertian { albedo }
     }
 }
 
+// How much of the light a metal reflects, depending on the angle it arrives at.
+enum Reflectance {
+    // Schlick's approximation, given the colour reflected head on.
+    Tinted(Arc<Box<dyn Texture>>),
+    // The Fresnel equations for a conductor with the complex refractive index eta + ik,
+    // one per colour channel.
+    Conductor { eta: Colour, k: Colour },
+}
+
+impl Reflectance {
+    fn value(&self, rec: &HitRecord, cos_theta: f64) -> Colour {
+        match self {
+            Reflectance::Tinted(albedo) => {
+                let f0 = albedo.value(rec.u, rec.v, rec.point);
+                f0 + (Colour::new(1.0, 1.0, 1.0) - f0) * (1.0 - cos_theta).max(0.0).powi(5)
+            }
+            Reflectance::Conductor { eta, k } => Colour::new(
+                conductor_fresnel(cos_theta, eta.x, k.x),
+                conductor_fresnel(cos_theta, eta.y, k.y),
+                conductor_fresnel(cos_theta, eta.z, k.z),
+            ),
+        }
+    }
+}
+
+// A rough conductor, modelled as a surface of tiny mirrors whose slopes follow the
+// GGX distribution. Reflections are sampled from the mirrors visible from the
+// incoming direction, and light bouncing between mirrors more than once is lost, so
+// very rough metals come out darker than they should. A roughness of zero is a
+// perfect mirror.
 pub struct Metal {
-    albedo: Colour,
-    fuzz: f64,
+    reflectance: Reflectance,
+    // The GGX width, the square of the roughness, which makes roughness look linear.
+    alpha: f64,
 }
 
 impl Material for Metal {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> RayRecord {
-        // might have to change it so that the ones that are pointed backwards are not scattered.
-        let reflected =
-            reflect(r_in.direction, rec.normal).normalize() + (random_unit_vector() * self.fuzz);
+        let unit_direction = r_in.direction.normalize();
+        if self.alpha < MIRROR_ALPHA {
+            let cos_theta = dot(-unit_direction, rec.normal).clamp(0.0, 1.0);
+            return RayRecord::new(
+                self.reflectance.value(rec, cos_theta),
+                Some(Ray::with_time(
+                    rec.point,
+                    reflect(unit_direction, rec.normal),
+                    r_in.time,
+                )),
+            );
+        }
+
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-unit_direction);
+        if wo.z <= 0.0 {
+            return RayRecord::new(Colour::new(0.0, 0.0, 0.0), None);
+        }
+        let microfacet = sample_visible_normal(wo, self.alpha);
+        let wi = reflect(-wo, microfacet);
+        // Reflected off a mirror facing away from the surface into it.
+        if wi.z <= 0.0 {
+            return RayRecord::new(Colour::new(0.0, 0.0, 0.0), None);
+        }
 
-        RayRecord::new(self.albedo, Some(Ray::new(rec.point, reflected)))
+        // The BSDF times the cosine over the pdf leaves the Fresnel term and the
+        // fraction of the reflected light not shadowed by other mirrors.
+        let fresnel = self.reflectance.value(rec, dot(wo, microfacet));
+        let shadowing = smith_g(wo, wi, self.alpha) / smith_g1(wo, self.alpha);
+        RayRecord::sampled(
+            fresnel * shadowing,
+            Ray::with_time(rec.point, onb.to_world(wi), r_in.time),
+            visible_normal_pdf(wo, wi, self.alpha),
+        )
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vector) -> Colour {
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-r_in.direction.normalize());
+        let wi = onb.to_local(direction.normalize());
+        if self.alpha < MIRROR_ALPHA || wo.z <= 0.0 || wi.z <= 0.0 {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+        let microfacet = (wo + wi).normalize();
+        let fresnel = self.reflectance.value(rec, dot(wo, microfacet));
+        fresnel * (ggx_d(microfacet, self.alpha) * smith_g(wo, wi, self.alpha) / (4.0 * wo.z))
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vector) -> f64 {
+        let onb = Onb::new(rec.normal);
+        let wo = onb.to_local(-r_in.direction.normalize());
+        let wi = onb.to_local(direction.normalize());
+        if self.alpha < MIRROR_ALPHA || wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        visible_normal_pdf(wo, wi, self.alpha)
     }
 }
 
 impl Metal {
-    pub fn new(albedo: Colour, fuzz: f64) -> Self {
-        Metal { albedo, fuzz }
+    // A metal of the given colour head on, brightening towards white at grazing angles.
+    pub fn new(albedo: Colour, roughness: f64) -> Self {
+        Metal::from_texture(Arc::new(Box::new(SolidColour::new(albedo))), roughness)
+    }
+
+    pub fn from_texture(albedo: Arc<Box<dyn Texture>>, roughness: f64) -> Self {
+        Metal::with_reflectance(Reflectance::Tinted(albedo), roughness)
+    }
+
+    // A metal with the complex refractive index eta + ik, measured at the red, green
+    // and blue wavelengths.
+    pub fn conductor(eta: Colour, k: Colour, roughness: f64) -> Self {
+        Metal::with_reflectance(Reflectance::Conductor { eta, k }, roughness)
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Metal::conductor(
+            Colour::new(0.143, 0.375, 1.442),
+            Colour::new(3.983, 2.386, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Metal::conductor(
+            Colour::new(0.200, 0.924, 1.102),
+            Colour::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Metal::conductor(
+            Colour::new(1.657, 0.880, 0.521),
+            Colour::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Metal::conductor(
+            Colour::new(0.155, 0.117, 0.138),
+            Colour::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    fn with_reflectance(reflectance: Reflectance, roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Metal {
+            reflectance,
+            alpha: roughness * roughness,
+        }
     }
 }
 
@@ -86,7 +275,7 @@ impl Material for Dielectric {
 
         RayRecord::new(
             Colour::new(1.0, 1.0, 1.0),
-            Some(Ray::new(rec.point, direction)),
+            Some(Ray::with_time(rec.point, direction, r_in.time)),
         )
     }
 }
@@ -99,27 +288,181 @@ impl Dielectric {
 
 pub struct Light {
     albedo: Colour,
+    intensity: f64,
+    two_sided: bool,
+    // The material light is scattered by. Without one the light absorbs everything.
+    surface: Option<Arc<Box<dyn Material + Sync + Send>>>,
 }
 
 impl Material for Light {
-    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> RayRecord {
-        RayRecord::new(self.albedo, None)
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> RayRecord {
+        match &self.surface {
+            Some(surface) => surface.scatter(r_in, rec),
+            None => RayRecord::new(Colour::new(0.0, 0.0, 0.0), None),
+        }
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Colour {
+        match self.two_sided || rec.front_face {
+            true => self.albedo * self.intensity,
+            false => Colour::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vector) -> Colour {
+        match &self.surface {
+            Some(surface) => surface.eval(r_in, rec, direction),
+            None => Colour::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vector) -> f64 {
+        match &self.surface {
+            Some(surface) => surface.scattering_pdf(r_in, rec, direction),
+            None => 0.0,
+        }
     }
 }
 
 impl Light {
     pub fn new(albedo: Colour) -> Self {
-        Light { albedo }
+        Light {
+            albedo,
+            intensity: 1.0,
+            two_sided: true,
+            surface: None,
+        }
+    }
+
+    // Scales the emitted colour, allowing lights brighter than 1.0.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    // Only emit from the side the surface normal points towards.
+    pub fn one_sided(mut self) -> Self {
+        self.two_sided = false;
+        self
+    }
+
+    pub fn with_surface(mut self, surface: Arc<Box<dyn Material + Sync + Send>>) -> Self {
+        self.surface = Some(surface);
+        self
+    }
+}
+
+// The phase function of a participating medium: light is scattered equally in every
+// direction, whatever the normal. The albedo is the fraction that is scattered
+// rather than absorbed.
+pub struct Isotropic {
+    albedo: Arc<Box<dyn Texture>>,
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> RayRecord {
+        RayRecord::sampled(
+            self.albedo.value(rec.u, rec.v, rec.point),
+            Ray::with_time(rec.point, random_unit_vector(), r_in.time),
+            1.0 / (4.0 * PI),
+        )
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, _direction: Vector) -> Colour {
+        self.albedo.value(rec.u, rec.v, rec.point) / (4.0 * PI)
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vector) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+impl Isotropic {
+    pub fn new(albedo: Colour) -> Self {
+        Isotropic::from_texture(Arc::new(Box::new(SolidColour::new(albedo))))
+    }
+
+    pub fn from_texture(albedo: Arc<Box<dyn Texture>>) -> Self {
+        Isotropic { albedo }
+    }
+}
+
+// A phase function that favours scattering forwards for positive anisotropy, or back
+// the way the light came for negative, with zero the same as Isotropic. The
+// anisotropy is the average cosine of the angle the light is turned through, and is
+// kept just inside -1 to 1.
+pub struct HenyeyGreenstein {
+    albedo: Arc<Box<dyn Texture>>,
+    anisotropy: f64,
+}
+
+impl Material for HenyeyGreenstein {
+    // Inverts the distribution of the cosine of the angle to the incoming direction,
+    // which is uniform in the isotropic case.
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> RayRecord {
+        let g = self.anisotropy;
+        let cos_theta = match g.abs() < 1e-3 {
+            true => 1.0 - 2.0 * random::<f64>(),
+            false => {
+                let ratio = (1.0 - g * g) / (1.0 - g + 2.0 * g * random::<f64>());
+                (1.0 + g * g - ratio * ratio) / (2.0 * g)
+            }
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random::<f64>();
+        let local = Vector::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let direction = Onb::new(r_in.direction).to_world(local);
+
+        RayRecord::sampled(
+            self.albedo.value(rec.u, rec.v, rec.point),
+            Ray::with_time(rec.point, direction, r_in.time),
+            self.scattering_pdf(r_in, rec, direction),
+        )
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vector) -> Colour {
+        self.albedo.value(rec.u, rec.v, rec.point) * self.scattering_pdf(r_in, rec, direction)
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, _rec: &HitRecord, direction: Vector) -> f64 {
+        let g = self.anisotropy;
+        let cos_theta = dot(r_in.direction.normalize(), direction.normalize());
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
     }
 }
 
-fn random_unit_vector() -> Vector {
-    let random_vec = Vector::new(
-        random_range(-1.0..=1.0),
-        random_range(-1.0..=1.0),
-        random_range(-1.0..=1.0),
-    );
-    random_vec.normalize()
+impl HenyeyGreenstein {
+    pub fn new(albedo: Colour, anisotropy: f64) -> Self {
+        HenyeyGreenstein::from_texture(Arc::new(Box::new(SolidColour::new(albedo))), anisotropy)
+    }
+
+    pub fn from_texture(albedo: Arc<Box<dyn Texture>>, anisotropy: f64) -> Self {
+        HenyeyGreenstein {
+            albedo,
+            anisotropy: anisotropy.clamp(-0.99, 0.99),
+        }
+    }
+}
+
+// Uniformly distributed over the sphere, which makes normal + random_unit_vector()
+// cosine distributed about the normal.
+pub(crate) fn random_unit_vector() -> Vector {
+    loop {
+        let random_vec = Vector::new(
+            random_range(-1.0..=1.0),
+            random_range(-1.0..=1.0),
+            random_range(-1.0..=1.0),
+        );
+        let magnitude2 = random_vec.magnitude2();
+        if 1e-160 < magnitude2 && magnitude2 <= 1.0 {
+            return random_vec / magnitude2.sqrt();
+        }
+    }
 }
 
 fn near_zero(vec: Vector) -> bool {
@@ -138,8 +481,144 @@ fn refract(v: Vector, n: Vector, etai_over_etat: f64) -> Vector {
     r_out_perp + r_out_parallel
 }
 
+// Metals smoother than this are treated as perfect mirrors, whose reflections can only
+// be found by following them.
+const MIRROR_ALPHA: f64 = 1e-4;
+
+// The GGX distribution of microfacet normals, in a frame with the surface normal along z.
+fn ggx_d(microfacet: Vector, alpha: f64) -> f64 {
+    let alpha2 = alpha * alpha;
+    let denominator = microfacet.z * microfacet.z * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+// Smith's auxiliary function, from which the masking of microfacets seen from direction
+// follows.
+fn smith_lambda(direction: Vector, alpha: f64) -> f64 {
+    let cos2 = direction.z * direction.z;
+    let tan2 = (1.0 - cos2).max(0.0) / cos2;
+    ((1.0 + alpha * alpha * tan2).sqrt() - 1.0) / 2.0
+}
+
+// The fraction of the microfacets facing direction that are not hidden by others.
+fn smith_g1(direction: Vector, alpha: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(direction, alpha))
+}
+
+// The fraction visible from both directions, allowing for the correlation between the
+// two, which is largest when they are close.
+fn smith_g(wo: Vector, wi: Vector, alpha: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(wo, alpha) + smith_lambda(wi, alpha))
+}
+
+// Samples a microfacet normal in proportion to how much of it is visible from wo,
+// following Heitz's 2018 method: the surface is stretched to unit roughness, where the
+// visible normals are found by sampling a projected disk.
+fn sample_visible_normal(wo: Vector, alpha: f64) -> Vector {
+    let stretched = Vector::new(alpha * wo.x, alpha * wo.y, wo.z).normalize();
+    let length2 = stretched.x * stretched.x + stretched.y * stretched.y;
+    let t1 = match length2 > 0.0 {
+        true => Vector::new(-stretched.y, stretched.x, 0.0) / length2.sqrt(),
+        false => Vector::new(1.0, 0.0, 0.0),
+    };
+    let t2 = stretched.cross(t1);
+
+    let radius = random::<f64>().sqrt();
+    let phi = 2.0 * PI * random::<f64>();
+    let p1 = radius * phi.cos();
+    let s = 0.5 * (1.0 + stretched.z);
+    let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * radius * phi.sin();
+    let normal = t1 * p1 + t2 * p2 + stretched * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+    Vector::new(alpha * normal.x, alpha * normal.y, normal.z.max(0.0)).normalize()
+}
+
+// The solid angle density of sample_visible_normal reflecting wo into wi.
+fn visible_normal_pdf(wo: Vector, wi: Vector, alpha: f64) -> f64 {
+    let microfacet = (wo + wi).normalize();
+    smith_g1(wo, alpha) * ggx_d(microfacet, alpha) / (4.0 * wo.z)
+}
+
+// The fraction of unpolarised light reflected by a conductor with the complex
+// refractive index eta + ik, at one wavelength.
+fn conductor_fresnel(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos2.sqrt() * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rs + rp)
+}
+
 fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
     let mut r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
     r0 = r0 * r0;
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng, vector::Point};
+
+    // A hit on a floor facing +y, by a ray coming down at an angle.
+    fn floor_hit() -> (Ray, HitRecord) {
+        let ray = Ray::new(Point::new(-1.0, 1.0, -0.3), Vector::new(1.0, -1.0, 0.3));
+        let rec = HitRecord {
+            point: Point::new(0.0, 0.0, 0.0),
+            normal: Vector::new(0.0, 1.0, 0.0),
+            t: 1.0,
+            u: 0.5,
+            v: 0.5,
+            front_face: true,
+            material: Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)))),
+        };
+        (ray, rec)
+    }
+
+    #[test]
+    fn rough_metals_weigh_samples_by_their_own_pdf() {
+        rng::seed(5);
+        let (ray, rec) = floor_hit();
+        for metal in [
+            Metal::gold(0.4),
+            Metal::new(Colour::new(0.9, 0.6, 0.3), 0.8),
+        ] {
+            let mut sampled = 0;
+            for _ in 0..1000 {
+                let record = metal.scatter(&ray, &rec);
+                let (Some(scattered), Some(pdf)) = (record.ray, record.pdf) else {
+                    continue;
+                };
+                sampled += 1;
+                let direction = scattered.direction;
+                let expected_pdf = metal.scattering_pdf(&ray, &rec, direction);
+                assert!(
+                    (pdf - expected_pdf).abs() <= 1e-9 * pdf,
+                    "{pdf} != {expected_pdf}"
+                );
+                let weight = metal.eval(&ray, &rec, direction) / pdf;
+                assert!(weight.approx_eq(&record.colour, 1e-9), "{weight:?}");
+            }
+            assert!(sampled > 500, "only {sampled} directions were sampled");
+        }
+    }
+
+    #[test]
+    fn mirrors_are_not_sampled() {
+        let (ray, rec) = floor_hit();
+        let mirror = Metal::new(Colour::new(0.9, 0.9, 0.9), 0.0);
+        let record = mirror.scatter(&ray, &rec);
+        assert!(record.pdf.is_none());
+        let reflected = record.ray.unwrap().direction;
+        assert_eq!(reflected, Vector::new(1.0, 1.0, 0.3).normalize());
+        assert_eq!(mirror.scattering_pdf(&ray, &rec, reflected), 0.0);
+    }
+}
@@ -0,0 +1,398 @@
+use std::sync::Arc;
+
+use crate::{
+    Colour,
+    aabb::Aabb,
+    density_grid::DensityGrid,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::{HenyeyGreenstein, Isotropic, Material},
+    ray::Ray,
+    rng::random,
+    texture::{SolidColour, Texture},
+};
+
+// Smoke, fog or mist filling a closed boundary with a constant density. Rather than
+// being hit at a surface, a ray scatters at a random distance into the medium, so
+// thin stretches are mostly passed through. The boundary must be solid; its own
+// material is ignored, and an object without spans is empty.
+pub struct ConstantMedium {
+    boundary: Arc<Box<dyn Hittable>>,
+    density: f64,
+    phase_function: Arc<Box<dyn Material + Sync + Send>>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<Box<dyn Hittable>>, density: f64, albedo: Colour) -> Self {
+        ConstantMedium::from_texture(
+            boundary,
+            density,
+            Arc::new(Box::new(SolidColour::new(albedo))),
+        )
+    }
+
+    pub fn from_texture(
+        boundary: Arc<Box<dyn Hittable>>,
+        density: f64,
+        albedo: Arc<Box<dyn Texture>>,
+    ) -> Self {
+        ConstantMedium {
+            boundary,
+            density,
+            phase_function: Arc::new(Box::new(Isotropic::from_texture(albedo))),
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    // One scattering distance is drawn and used up across every stretch of the ray
+    // inside the boundary, so concave and disjoint boundaries work, as do rays
+    // starting inside.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let speed = ray.direction.magnitude();
+        let mut distance = scattering_distance(self.density);
+        for inside in self.inside(ray, ray_t) {
+            let length = inside.size() * speed;
+            if distance < length {
+                return Some(scattering_record(
+                    ray,
+                    inside.min + distance / speed,
+                    &self.phase_function,
+                ));
+            }
+            distance -= length;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn is_medium(&self) -> bool {
+        true
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        let length: f64 = self.inside(ray, ray_t).iter().map(Interval::size).sum();
+        (-self.density * length * ray.direction.magnitude()).exp()
+    }
+}
+
+impl ConstantMedium {
+    // The stretches of the ray within ray_t that are inside the boundary, in order.
+    fn inside(&self, ray: &Ray, ray_t: Interval) -> Vec<Interval> {
+        self.boundary
+            .spans(ray)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|span| Interval::new(span.enter.t.max(ray_t.min), span.exit.t.min(ray_t.max)))
+            .filter(|inside| inside.min < inside.max)
+            .collect()
+    }
+}
+
+// Fog filling the whole scene, or only the space below height if one is set. Fog
+// without a height hides the environment, since no ray escapes it: the scene is then
+// only lit by the lights in it.
+pub struct Fog {
+    density: f64,
+    height: f64,
+    phase_function: Arc<Box<dyn Material + Sync + Send>>,
+}
+
+impl Fog {
+    pub fn new(density: f64, albedo: Colour) -> Self {
+        Fog {
+            density,
+            height: f64::INFINITY,
+            phase_function: Arc::new(Box::new(Isotropic::new(albedo))),
+        }
+    }
+
+    pub fn with_height(mut self, height: f64) -> Self {
+        self.height = height;
+        self
+    }
+}
+
+impl Hittable for Fog {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let inside = self.inside(ray, ray_t)?;
+        let t = inside.min + scattering_distance(self.density) / ray.direction.magnitude();
+        match inside.surounds(t) {
+            true => Some(scattering_record(ray, t, &self.phase_function)),
+            false => None,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+
+    fn is_medium(&self) -> bool {
+        true
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        match self.inside(ray, ray_t) {
+            Some(inside) => (-self.density * inside.size() * ray.direction.magnitude()).exp(),
+            None => 1.0,
+        }
+    }
+}
+
+impl Fog {
+    // The part of the ray within ray_t below the top of the fog.
+    fn inside(&self, ray: &Ray, ray_t: Interval) -> Option<Interval> {
+        let mut ray_t = ray_t;
+        let crossing = (self.height - ray.origin.y) / ray.direction.y;
+        match ray.direction.y {
+            y if y > 0.0 => ray_t.max = ray_t.max.min(crossing),
+            y if y < 0.0 => ray_t.min = ray_t.min.max(crossing),
+            _ if ray.origin.y > self.height => return None,
+            _ => {}
+        }
+        match ray_t.min < ray_t.max {
+            true => Some(ray_t),
+            false => None,
+        }
+    }
+}
+
+// A medium whose density varies through space, given by a grid scaled by density.
+// Scattering is found by delta tracking: the ray takes steps as if the medium were
+// everywhere as dense as its densest point, and at each one scatters with the
+// probability that the medium really is that dense there. Otherwise the collision
+// was with nothing and it carries on. Shadow rays are instead dimmed at every step
+// by the chance of passing through, which is ratio tracking. Both are unbiased.
+pub struct GridMedium {
+    grid: Arc<DensityGrid>,
+    density: f64,
+    // The density at the densest point of the grid, which bounds it everywhere.
+    majorant: f64,
+    albedo: Arc<Box<dyn Texture>>,
+    phase_function: Arc<Box<dyn Material + Sync + Send>>,
+}
+
+impl GridMedium {
+    pub fn new(grid: Arc<DensityGrid>, density: f64, albedo: Colour) -> Self {
+        GridMedium::from_texture(grid, density, Arc::new(Box::new(SolidColour::new(albedo))))
+    }
+
+    pub fn from_texture(
+        grid: Arc<DensityGrid>,
+        density: f64,
+        albedo: Arc<Box<dyn Texture>>,
+    ) -> Self {
+        GridMedium {
+            majorant: grid.max_density() * density,
+            grid,
+            density,
+            phase_function: Arc::new(Box::new(Isotropic::from_texture(albedo.clone()))),
+            albedo,
+        }
+    }
+
+    // Scatters light by a Henyey-Greenstein phase function instead of equally in every
+    // direction: mostly forwards for positive anisotropy, as in clouds, and backwards
+    // for negative.
+    pub fn with_anisotropy(mut self, anisotropy: f64) -> Self {
+        self.phase_function = Arc::new(Box::new(HenyeyGreenstein::from_texture(
+            self.albedo.clone(),
+            anisotropy,
+        )));
+        self
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let inside = self.grid.bounds().clip(ray, ray_t)?;
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let speed = ray.direction.magnitude();
+        let mut t = inside.min;
+        loop {
+            t += scattering_distance(self.majorant) / speed;
+            if t >= inside.max {
+                return None;
+            }
+            let density = self.density * self.grid.density(ray.at(t));
+            if random::<f64>() * self.majorant < density {
+                return Some(scattering_record(ray, t, &self.phase_function));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.grid.bounds()
+    }
+
+    fn is_medium(&self) -> bool {
+        true
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        let Some(inside) = self.grid.bounds().clip(ray, ray_t) else {
+            return 1.0;
+        };
+        if self.majorant <= 0.0 {
+            return 1.0;
+        }
+        let speed = ray.direction.magnitude();
+        let mut transmittance = 1.0;
+        let mut t = inside.min;
+        loop {
+            t += scattering_distance(self.majorant) / speed;
+            if t >= inside.max {
+                return transmittance;
+            }
+            let density = self.density * self.grid.density(ray.at(t));
+            transmittance *= 1.0 - density / self.majorant;
+        }
+    }
+}
+
+// How far light travels through a medium of the given density before scattering,
+// which is exponentially distributed.
+fn scattering_distance(density: f64) -> f64 {
+    -(1.0 - random::<f64>()).ln() / density
+}
+
+// Scattering in a medium has no surface, so the normal is arbitrary and only faces
+// the ray to keep to the convention.
+fn scattering_record(
+    ray: &Ray,
+    t: f64,
+    phase_function: &Arc<Box<dyn Material + Sync + Send>>,
+) -> HitRecord {
+    HitRecord {
+        point: ray.at(t),
+        normal: -ray.direction.normalize(),
+        t,
+        u: 0.0,
+        v: 0.0,
+        front_face: true,
+        material: phase_function.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng, sphere::Sphere, vector::Point, vector::Vector};
+
+    const SAMPLES: usize = 20000;
+
+    // Two voxels stacked in z with densities 0.25 and 1. A ray along x low in the
+    // bottom voxel sees a quarter of the densest point's density, so most of the
+    // collisions tracking makes there are with nothing.
+    fn layered_medium(density: f64) -> GridMedium {
+        let bounds = Aabb::from_points(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 1.0));
+        let grid = DensityGrid::new([1, 1, 2], vec![0.25, 1.0], bounds);
+        GridMedium::new(Arc::new(grid), density, Colour::new(1.0, 1.0, 1.0))
+    }
+
+    fn low_ray() -> Ray {
+        Ray::new(Point::new(-1.0, 0.5, 0.1), Vector::new(1.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn ratio_tracking_matches_beer_lambert() {
+        rng::seed(1);
+        let medium = layered_medium(2.0);
+        let universe = Interval::new(0.0, f64::INFINITY);
+        let mean = (0..SAMPLES)
+            .map(|_| medium.transmittance(&low_ray(), universe))
+            .sum::<f64>()
+            / SAMPLES as f64;
+        // Density 0.25 * 2 over a length of 1.
+        assert!((mean - (-0.5f64).exp()).abs() < 0.01, "{mean}");
+
+        // Only the part of the ray inside ray_t counts.
+        let half = Interval::new(0.0, 1.5);
+        let mean = (0..SAMPLES)
+            .map(|_| medium.transmittance(&low_ray(), half))
+            .sum::<f64>()
+            / SAMPLES as f64;
+        assert!((mean - (-0.25f64).exp()).abs() < 0.01, "{mean}");
+    }
+
+    #[test]
+    fn delta_tracking_collides_as_often_as_the_density() {
+        rng::seed(2);
+        let medium = layered_medium(2.0);
+        let universe = Interval::new(0.0, f64::INFINITY);
+        let mut hits = 0;
+        let mut first_half = 0;
+        for _ in 0..SAMPLES {
+            if let Some(rec) = medium.hit(&low_ray(), universe) {
+                hits += 1;
+                assert!((1.0..=2.0).contains(&rec.t));
+                if rec.t < 1.5 {
+                    first_half += 1;
+                }
+            }
+        }
+        let fraction = hits as f64 / SAMPLES as f64;
+        assert!(
+            (fraction - (1.0 - (-0.5f64).exp())).abs() < 0.01,
+            "{fraction}"
+        );
+        let fraction = first_half as f64 / SAMPLES as f64;
+        assert!(
+            (fraction - (1.0 - (-0.25f64).exp())).abs() < 0.01,
+            "{fraction}"
+        );
+    }
+
+    #[test]
+    fn empty_grids_are_transparent() {
+        let bounds = Aabb::from_points(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 1.0));
+        let grid = DensityGrid::new([1, 1, 1], vec![0.0], bounds);
+        let medium = GridMedium::new(Arc::new(grid), 5.0, Colour::new(1.0, 1.0, 1.0));
+        let universe = Interval::new(0.0, f64::INFINITY);
+        assert!(medium.hit(&low_ray(), universe).is_none());
+        assert_eq!(medium.transmittance(&low_ray(), universe), 1.0);
+    }
+
+    // Fills the unit sphere around the origin.
+    fn smoke(density: f64) -> ConstantMedium {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Isotropic::new(Colour::new(1.0, 1.0, 1.0))));
+        let boundary = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0, material);
+        ConstantMedium::new(
+            Arc::new(Box::new(boundary)),
+            density,
+            Colour::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn constant_media_follow_beer_lambert() {
+        let medium = smoke(0.7);
+        // Twice as fast as a unit ray, so inside the sphere from t = 2 to 3.
+        let ray = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(2.0, 0.0, 0.0));
+        let universe = Interval::new(0.0, f64::INFINITY);
+        let transmittance = medium.transmittance(&ray, universe);
+        assert!((transmittance - (-1.4f64).exp()).abs() < 1e-9);
+        let halfway = medium.transmittance(&ray, Interval::new(0.0, 2.5));
+        assert!((halfway - (-0.7f64).exp()).abs() < 1e-9);
+
+        let from_centre = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let transmittance = medium.transmittance(&from_centre, universe);
+        assert!((transmittance - (-0.7f64).exp()).abs() < 1e-9);
+
+        rng::seed(4);
+        let hits = (0..SAMPLES)
+            .filter(|_| medium.hit(&ray, universe).is_some())
+            .count();
+        let fraction = hits as f64 / SAMPLES as f64;
+        assert!(
+            (fraction - (1.0 - (-1.4f64).exp())).abs() < 0.01,
+            "{fraction}"
+        );
+    }
+}
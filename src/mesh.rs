@@ -0,0 +1,352 @@
+use std::{error::Error, fmt::Display, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    flat_bvh::FlatBvh,
+    hittable::{HitRecord, Hittable, area_to_solid_angle_pdf},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    rng::random,
+    triangle::{hit_triangle, random_triangle_point, triangle_area, triangle_bounding_box},
+    vector::{Point, Vector},
+};
+
+// Vertex and index buffers shared by every triangle of a mesh.
+pub struct MeshData {
+    pub positions: Vec<Point>,
+    pub normals: Option<Vec<Vector>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl MeshData {
+    fn vertices(&self, triangle: usize) -> [Point; 3] {
+        let [a, b, c] = self.indices[triangle];
+        [self.positions[a], self.positions[b], self.positions[c]]
+    }
+
+    fn normals(&self, triangle: usize) -> Option<[Vector; 3]> {
+        let [a, b, c] = self.indices[triangle];
+        self.normals
+            .as_ref()
+            .map(|normals| [normals[a], normals[b], normals[c]])
+    }
+
+    fn uvs(&self, triangle: usize) -> Option<[(f64, f64); 3]> {
+        let [a, b, c] = self.indices[triangle];
+        self.uvs.as_ref().map(|uvs| [uvs[a], uvs[b], uvs[c]])
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MeshError {
+    IndexOutOfRange {
+        triangle: usize,
+        index: usize,
+        vertex_count: usize,
+    },
+    AttributeCount {
+        attribute: &'static str,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshError::IndexOutOfRange {
+                triangle,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "triangle {triangle} uses vertex {index} but the mesh has only {vertex_count}"
+            ),
+            MeshError::AttributeCount {
+                attribute,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected one {attribute} per vertex ({expected}), found {found}"
+            ),
+        }
+    }
+}
+
+impl Error for MeshError {}
+
+pub struct TriangleMesh {
+    data: MeshData,
+    // Indices of the triangles in data.indices.
+    bvh: FlatBvh<u32>,
+    material: Arc<Box<dyn Material + Sync + Send>>,
+    // Running totals of the triangle areas, for sampling points on the mesh.
+    area_cdf: Vec<f64>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Result<Self, MeshError> {
+        Self::from_data(
+            MeshData {
+                positions,
+                normals: None,
+                uvs: None,
+                indices,
+            },
+            material,
+        )
+    }
+
+    pub fn with_normals(
+        positions: Vec<Point>,
+        normals: Vec<Vector>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Result<Self, MeshError> {
+        Self::from_data(
+            MeshData {
+                positions,
+                normals: Some(normals),
+                uvs: None,
+                indices,
+            },
+            material,
+        )
+    }
+
+    pub fn from_data(
+        data: MeshData,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Result<Self, MeshError> {
+        let vertex_count = data.positions.len();
+        for (triangle, indices) in data.indices.iter().enumerate() {
+            if let Some(&index) = indices.iter().find(|&&index| index >= vertex_count) {
+                return Err(MeshError::IndexOutOfRange {
+                    triangle,
+                    index,
+                    vertex_count,
+                });
+            }
+        }
+        for (attribute, len) in [
+            ("normal", data.normals.as_ref().map(Vec::len)),
+            ("uv", data.uvs.as_ref().map(Vec::len)),
+        ] {
+            match len {
+                Some(found) if found != vertex_count => {
+                    return Err(MeshError::AttributeCount {
+                        attribute,
+                        expected: vertex_count,
+                        found,
+                    });
+                }
+                _ => {}
+            }
+        }
+        // Triangles are referred to by u32 indices in the BVH.
+        let triangle_count =
+            u32::try_from(data.indices.len()).expect("mesh has more than u32::MAX triangles");
+
+        let bvh = FlatBvh::build(
+            (0..triangle_count).collect(),
+            |&triangle| triangle_bounding_box(data.vertices(triangle as usize)),
+            4,
+        );
+
+        let area_cdf = (0..data.indices.len())
+            .scan(0.0, |total, index| {
+                *total += triangle_area(data.vertices(index));
+                Some(*total)
+            })
+            .collect();
+
+        Ok(TriangleMesh {
+            data,
+            bvh,
+            material,
+            area_cdf,
+        })
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.data
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.data.indices.len()
+    }
+
+    pub fn area(&self) -> f64 {
+        self.area_cdf.last().copied().unwrap_or(0.0)
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.bvh.hit_with(ray, ray_t, |&triangle, interval| {
+            let triangle = triangle as usize;
+            hit_triangle(
+                ray,
+                interval,
+                self.data.vertices(triangle),
+                self.data.normals(triangle),
+                self.data.uvs(triangle),
+                &self.material,
+            )
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounds()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    // Points are sampled uniformly over the whole surface of the mesh.
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        match self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) {
+            Some(rec) if self.area() > 0.0 => {
+                area_to_solid_angle_pdf(1.0 / self.area(), &rec, direction)
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn random(&self, origin: Point) -> Vector {
+        if self.area_cdf.is_empty() {
+            return Vector::new(0.0, 0.0, 0.0);
+        }
+        let target = random::<f64>() * self.area();
+        let index = self
+            .area_cdf
+            .partition_point(|&total| total < target)
+            .min(self.area_cdf.len() - 1);
+        random_triangle_point(self.data.vertices(index)) - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    fn grey() -> Arc<Box<dyn Material + Sync + Send>> {
+        Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))))
+    }
+
+    fn unit_triangle(normals: Option<Vec<Vector>>, uvs: Option<Vec<(f64, f64)>>) -> TriangleMesh {
+        let data = MeshData {
+            positions: vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+            ],
+            normals,
+            uvs,
+            indices: vec![[0, 1, 2]],
+        };
+        TriangleMesh::from_data(data, grey()).unwrap()
+    }
+
+    // Straight down onto the point with barycentric weights 0.5, 0.2 and 0.3.
+    fn hit_unit_triangle(mesh: &TriangleMesh) -> HitRecord {
+        let ray = Ray::new(Point::new(0.2, 0.3, 1.0), Vector::new(0.0, 0.0, -1.0));
+        mesh.hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should hit the triangle")
+    }
+
+    #[test]
+    fn hits_report_barycentric_coordinates() {
+        let rec = hit_unit_triangle(&unit_triangle(None, None));
+        assert!((rec.t - 1.0).abs() < 1e-12);
+        assert!((rec.u - 0.2).abs() < 1e-12);
+        assert!((rec.v - 0.3).abs() < 1e-12);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn smooth_normals_are_interpolated() {
+        let normals = vec![
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(1.0, 0.0, 1.0).normalize(),
+            Vector::new(0.0, 1.0, 1.0).normalize(),
+        ];
+        let expected = (0.5 * normals[0] + 0.2 * normals[1] + 0.3 * normals[2]).normalize();
+        let rec = hit_unit_triangle(&unit_triangle(Some(normals), None));
+        assert_eq!(rec.normal, expected);
+    }
+
+    #[test]
+    fn uvs_are_interpolated() {
+        let uvs = vec![(0.5, 0.5), (1.0, 0.5), (0.5, 1.0)];
+        let rec = hit_unit_triangle(&unit_triangle(None, Some(uvs)));
+        assert!((rec.u - 0.6).abs() < 1e-12);
+        assert!((rec.v - 0.65).abs() < 1e-12);
+    }
+
+    #[test]
+    fn the_nearest_of_many_triangles_is_hit() {
+        // Ten stacked copies of a square, one unit apart along z.
+        let mut positions = Vec::new();
+        let mut indices = Vec::new();
+        for layer in 0..10 {
+            let z = layer as f64;
+            let first = positions.len();
+            positions.extend([
+                Point::new(0.0, 0.0, z),
+                Point::new(1.0, 0.0, z),
+                Point::new(1.0, 1.0, z),
+                Point::new(0.0, 1.0, z),
+            ]);
+            indices.push([first, first + 1, first + 2]);
+            indices.push([first, first + 2, first + 3]);
+        }
+        let mesh = TriangleMesh::new(positions, indices, grey()).unwrap();
+        assert_eq!(mesh.triangle_count(), 20);
+        assert!((mesh.area() - 10.0).abs() < 1e-12);
+
+        let ray = Ray::new(Point::new(0.7, 0.2, 20.0), Vector::new(0.0, 0.0, -1.0));
+        let rec = mesh.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((rec.t - 11.0).abs() < 1e-12);
+        let rec = mesh.hit(&ray, Interval::new(11.5, f64::INFINITY)).unwrap();
+        assert!((rec.t - 12.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        let positions = vec![Point::new(0.0, 0.0, 0.0); 3];
+        let error = TriangleMesh::new(positions.clone(), vec![[0, 1, 2], [0, 3, 1]], grey());
+        assert_eq!(
+            error.err(),
+            Some(MeshError::IndexOutOfRange {
+                triangle: 1,
+                index: 3,
+                vertex_count: 3
+            })
+        );
+
+        let normals = vec![Vector::new(0.0, 0.0, 1.0); 2];
+        let error = TriangleMesh::with_normals(positions, normals, vec![[0, 1, 2]], grey());
+        assert_eq!(
+            error.err(),
+            Some(MeshError::AttributeCount {
+                attribute: "normal",
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span},
+    instance::{to_local, to_world},
+    interval::Interval,
+    ray::Ray,
+    transform::{Decomposed, Transform},
+    vector::{Point, Vector},
+};
+
+// Moves any object from one placement at time 0 to another at time 1. In between it
+// turns at a constant rate from the first rotation to the second while its position
+// and scale change linearly. Before time 0 and after time 1 it stays at either end.
+pub struct Moving {
+    object: Arc<Box<dyn Hittable>>,
+    start: Decomposed,
+    end: Decomposed,
+    bbox: Aabb,
+}
+
+impl Moving {
+    // Panics if one placement mirrors the object and the other does not, since the
+    // object would be flattened part way between them.
+    pub fn new(object: Arc<Box<dyn Hittable>>, start: Transform, end: Transform) -> Self {
+        let (start, end) = (start.decompose(), end.decompose());
+        assert!(
+            start.is_reflection() == end.is_reflection(),
+            "cannot move between a mirrored and an unmirrored placement"
+        );
+        let bbox = swept_box(&object.bounding_box(), &start, &end);
+        Moving {
+            object,
+            start,
+            end,
+            bbox,
+        }
+    }
+
+    pub fn transform(&self, time: f64) -> Transform {
+        interpolate(&self.start, &self.end, time.clamp(0.0, 1.0))
+    }
+}
+
+impl Hittable for Moving {
+    // Takes the ray into the object's space as it is placed at the ray's time.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let transform = self.transform(ray.time);
+        let rec = self.object.hit(&to_local(&transform, ray), ray_t)?;
+        Some(to_world(&transform, rec))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_medium(&self) -> bool {
+        self.object.is_medium()
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        let transform = self.transform(ray.time);
+        self.object.transmittance(&to_local(&transform, ray), ray_t)
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        let transform = self.transform(ray.time);
+        let spans = self.object.spans(&to_local(&transform, ray))?;
+        Some(
+            spans
+                .into_iter()
+                .map(|span| Span {
+                    enter: to_world(&transform, span.enter),
+                    exit: to_world(&transform, span.exit),
+                })
+                .collect(),
+        )
+    }
+}
+
+// The scale part of a placement is a stretch that is positive (or, for a mirrored
+// placement, negative) in every direction, and a blend of two such stretches is too,
+// so it can always be inverted.
+fn interpolate(start: &Decomposed, end: &Decomposed, t: f64) -> Transform {
+    start
+        .lerp(end, t)
+        .transform()
+        .expect("interpolated placements are never flat")
+}
+
+// The box enclosing the object at every time from 0 to 1. The corners of its box are
+// placed at many times along the way, and the result grown by the furthest any
+// corner moves between two of them, which covers the arcs they follow in between.
+fn swept_box(bbox: &Aabb, start: &Decomposed, end: &Decomposed) -> Aabb {
+    const STEPS: usize = 64;
+    if !bbox.is_bounded() {
+        return Aabb::UNIVERSE;
+    }
+    let corners = |t: f64| {
+        let transform = interpolate(start, end, t);
+        bbox.corners().map(|corner| transform.point(corner))
+    };
+
+    let mut min = Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+    let mut max = -min;
+    let mut furthest: f64 = 0.0;
+    let mut previous = corners(0.0);
+    for step in 0..=STEPS {
+        let current = corners(step as f64 / STEPS as f64);
+        for (point, last) in current.into_iter().zip(previous) {
+            min = min.min(point);
+            max = max.max(point);
+            furthest = furthest.max((point - last).magnitude());
+        }
+        previous = current;
+    }
+    let padding = Vector::new(furthest, furthest, furthest);
+    Aabb::from_points(min - padding, max + padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Colour,
+        box_shape::BoxShape,
+        material::{Lambertian, Material},
+        vector::Vector,
+    };
+
+    fn unit_box() -> Arc<Box<dyn Hittable>> {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        Arc::new(Box::new(BoxShape::new(
+            Point::new(-0.5, -0.5, -0.5),
+            Point::new(0.5, 0.5, 0.5),
+            material,
+        )))
+    }
+
+    #[test]
+    fn decomposing_gives_back_the_transform() {
+        let sheared = Transform::rotate(Vector::new(0.0, 0.0, 1.0), 30.0)
+            .then(Transform::scale(Vector::new(3.0, 0.5, 1.0)))
+            .then(Transform::translate(Vector::new(1.0, 2.0, 3.0)));
+        let mirrored = Transform::scale(Vector::new(-2.0, 1.0, 1.0))
+            .then(Transform::rotate(Vector::new(1.0, 1.0, 0.0), 70.0));
+        for transform in [sheared, mirrored, Transform::IDENTITY] {
+            let rebuilt = transform.decompose().transform().unwrap();
+            assert!(rebuilt.matrix().approx_eq(transform.matrix(), 1e-9));
+            let identity = *rebuilt.inverse().matrix() * *transform.matrix();
+            assert!(identity.approx_eq(&crate::vector::Matrix4::IDENTITY, 1e-9));
+        }
+        assert!(mirrored.decompose().is_reflection());
+        assert!(!sheared.decompose().is_reflection());
+    }
+
+    #[test]
+    fn rotations_turn_rather_than_squash() {
+        let end = Transform::rotate(Vector::new(0.0, 1.0, 0.0), 90.0)
+            .then(Transform::translate(Vector::new(4.0, 0.0, 0.0)));
+        let moving = Moving::new(unit_box(), Transform::IDENTITY, end);
+
+        let halfway = moving.transform(0.5);
+        let turned = halfway.vector(Vector::new(1.0, 0.0, 0.0));
+        let expected = Vector::new(1.0, 0.0, -1.0).normalize();
+        assert!(turned.approx_eq(&expected, 1e-9), "{turned:?}");
+        let centre = halfway.point(Point::new(0.0, 0.0, 0.0));
+        assert!(centre.approx_eq(&Point::new(2.0, 0.0, 0.0), 1e-9));
+
+        // Outside the shutter times the object stays at the ends.
+        let after = moving.transform(2.0).point(Point::new(0.0, 0.0, 0.0));
+        assert!(after.approx_eq(&Point::new(4.0, 0.0, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn bounding_box_covers_the_whole_motion() {
+        let end = Transform::scale(Vector::new(2.0, 1.0, 1.0))
+            .then(Transform::rotate(Vector::new(1.0, 2.0, 3.0), 170.0))
+            .then(Transform::translate(Vector::new(3.0, -1.0, 2.0)));
+        let start = Transform::translate(Vector::new(0.0, 5.0, 0.0));
+        let moving = Moving::new(unit_box(), start, end);
+        let bbox = moving.bounding_box();
+        let corners =
+            Aabb::from_points(Point::new(-0.5, -0.5, -0.5), Point::new(0.5, 0.5, 0.5)).corners();
+        for step in 0..=1000 {
+            let transform = moving.transform(step as f64 / 1000.0);
+            for corner in corners {
+                let point = transform.point(corner);
+                assert!(
+                    bbox.x.contains(point.x)
+                        && bbox.y.contains(point.y)
+                        && bbox.z.contains(point.z),
+                    "{point:?} outside {bbox:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rays_hit_the_object_where_it_is_at_their_time() {
+        let end = Transform::translate(Vector::new(10.0, 0.0, 0.0));
+        let moving = Moving::new(unit_box(), Transform::IDENTITY, end);
+        let ray =
+            |time| Ray::with_time(Point::new(5.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), time);
+        let universe = Interval::new(0.001, f64::INFINITY);
+        assert!(moving.hit(&ray(0.0), universe).is_none());
+        let rec = moving
+            .hit(&ray(0.5), universe)
+            .expect("the box is under the ray halfway");
+        assert!(rec.point.approx_eq(&Point::new(5.0, 0.0, 0.5), 1e-9));
+        assert!(rec.normal.approx_eq(&Vector::new(0.0, 0.0, 1.0), 1e-9));
+    }
+}
@@ -0,0 +1,714 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    Colour,
+    hittable_list::HittableList,
+    material::{Dielectric, Lambertian, Light, Material, Metal},
+    mesh::{MeshData, TriangleMesh},
+    texture::{ImageTexture, SolidColour, Texture, WrapMode},
+    vector::{Point, Vector},
+};
+
+#[derive(Debug)]
+pub struct ObjError {
+    pub path: PathBuf,
+    // None when the error is not tied to a line, such as the file failing to open.
+    pub line: Option<usize>,
+    pub message: String,
+    // The error in another file that led to this one, such as a material library.
+    pub cause: Option<Box<ObjError>>,
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message)?,
+            None => write!(f, "{}: {}", self.path.display(), self.message)?,
+        }
+        match &self.cause {
+            Some(cause) => write!(f, ": {cause}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn Error + 'static))
+    }
+}
+
+// A material parsed from a .mtl file. Only the statements the renderer can use are kept.
+#[derive(Clone, Debug)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub diffuse: Colour,
+    pub specular: Colour,
+    pub shininess: f64,
+    pub refraction_index: f64,
+    pub dissolve: f64,
+    pub emission: Colour,
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl MtlMaterial {
+    fn new(name: &str) -> Self {
+        MtlMaterial {
+            name: name.to_string(),
+            diffuse: Colour::new(0.8, 0.8, 0.8),
+            specular: Colour::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            refraction_index: 1.5,
+            dissolve: 1.0,
+            emission: Colour::new(0.0, 0.0, 0.0),
+            diffuse_map: None,
+        }
+    }
+
+    // Picks the closest of the renderer's materials: emissive surfaces become lights,
+    // transparent ones glass, mostly specular ones metal and everything else diffuse.
+    // Fails if the diffuse texture cannot be loaded.
+    pub fn to_material(&self) -> Result<Arc<Box<dyn Material + Sync + Send>>, ObjError> {
+        let diffuse: Arc<Box<dyn Texture>> = match &self.diffuse_map {
+            Some(path) => Arc::new(Box::new(
+                ImageTexture::load(path, WrapMode::Repeat).map_err(|err| ObjError {
+                    path: path.clone(),
+                    line: None,
+                    message: err.to_string(),
+                    cause: None,
+                })?,
+            )),
+            None => Arc::new(Box::new(SolidColour::new(self.diffuse))),
+        };
+
+        let material: Box<dyn Material + Sync + Send> = if max_component(self.emission) > 0.0 {
+            Box::new(
+                Light::new(self.emission)
+                    .with_surface(Arc::new(Box::new(Lambertian::from_texture(diffuse)))),
+            )
+        } else if self.dissolve < 1.0 {
+            Box::new(Dielectric::new(self.refraction_index))
+        } else if max_component(self.specular) > max_component(self.diffuse) {
+            // Maps the Phong exponent onto a roughness, through the usual match between
+            // Phong and microfacet highlights: sharp highlights give a mirror.
+            let roughness = (2.0 / (self.shininess + 2.0)).powf(0.25).min(1.0);
+            Box::new(Metal::new(self.specular, roughness))
+        } else {
+            Box::new(Lambertian::from_texture(diffuse))
+        };
+        Ok(Arc::new(material))
+    }
+}
+
+// The faces of one group that share a material.
+pub struct ObjGroup {
+    pub name: String,
+    pub material: Option<String>,
+    pub mesh: TriangleMesh,
+}
+
+pub struct ObjModel {
+    pub groups: Vec<ObjGroup>,
+    pub materials: HashMap<String, MtlMaterial>,
+}
+
+impl ObjModel {
+    pub fn triangle_count(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.mesh.triangle_count())
+            .sum()
+    }
+
+    pub fn into_hittable_list(self) -> HittableList {
+        let mut list = HittableList::new();
+        for group in self.groups {
+            list.add(Arc::new(Box::new(group.mesh)));
+        }
+        list
+    }
+}
+
+// Loads a Wavefront .obj file along with any .mtl libraries it references. Faces
+// without a material use a grey Lambertian.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<ObjModel, ObjError> {
+    let default_material: Arc<Box<dyn Material + Sync + Send>> =
+        Arc::new(Box::new(Lambertian::new(Colour::new(0.8, 0.8, 0.8))));
+    load_obj_with_default(path, default_material)
+}
+
+pub fn load_obj_with_default(
+    path: impl AsRef<Path>,
+    default_material: Arc<Box<dyn Material + Sync + Send>>,
+) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    let source = read_file(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut positions: Vec<Point> = Vec::new();
+    let mut normals: Vec<Vector> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut builders: Vec<GroupBuilder> = Vec::new();
+    let mut current_group = String::from("default");
+    let mut current_material: Option<String> = None;
+    let mut current_builder: Option<usize> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let mut parser = LineParser::new(path, line_index + 1, line);
+        let Some(keyword) = parser.next_token() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => positions.push(parser.vector()?),
+            "vn" => normals.push(parser.vector()?),
+            "vt" => {
+                let u = parser.number()?;
+                let v = parser.optional_number()?.unwrap_or(0.0);
+                uvs.push((u, v));
+            }
+            "f" => {
+                let mut corners = Vec::new();
+                while let Some(token) = parser.next_token() {
+                    corners.push(parser.face_corner(
+                        token,
+                        positions.len(),
+                        uvs.len(),
+                        normals.len(),
+                    )?);
+                }
+                if corners.len() < 3 {
+                    return Err(parser.error("face needs at least three vertices"));
+                }
+
+                let builder_index = match current_builder {
+                    Some(index) => index,
+                    None => {
+                        builders.push(GroupBuilder::new(&current_group, &current_material));
+                        builders.len() - 1
+                    }
+                };
+                current_builder = Some(builder_index);
+                builders[builder_index].add_polygon(&corners, &positions, &uvs, &normals);
+            }
+            "g" | "o" => {
+                current_group = parser.rest().unwrap_or("default").to_string();
+                current_builder = None;
+            }
+            "usemtl" => {
+                let name = parser
+                    .rest()
+                    .ok_or_else(|| parser.error("missing material name"))?;
+                if !materials.contains_key(name) {
+                    return Err(parser.error(&format!("unknown material \"{name}\"")));
+                }
+                current_material = Some(name.to_string());
+                current_builder = None;
+            }
+            "mtllib" => {
+                let names = parser
+                    .rest()
+                    .ok_or_else(|| parser.error("missing file name"))?;
+                for name in names.split_whitespace() {
+                    let library = load_mtl(directory.join(name)).map_err(|error| ObjError {
+                        cause: Some(Box::new(error)),
+                        ..parser.error(&format!("could not load material library \"{name}\""))
+                    })?;
+                    for material in library {
+                        materials.insert(material.name.clone(), material);
+                    }
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry are not rendered.
+            _ => {}
+        }
+    }
+
+    let mut converted: HashMap<String, Arc<Box<dyn Material + Sync + Send>>> = HashMap::new();
+    let mut groups = Vec::new();
+    for builder in builders {
+        if builder.data.indices.is_empty() {
+            continue;
+        }
+        let material = match &builder.material {
+            Some(name) => match converted.get(name) {
+                Some(material) => material.clone(),
+                None => {
+                    let material = materials[name].to_material()?;
+                    converted.insert(name.clone(), material.clone());
+                    material
+                }
+            },
+            None => default_material.clone(),
+        };
+        let (name, material_name) = (builder.name.clone(), builder.material.clone());
+        groups.push(ObjGroup {
+            name,
+            material: material_name,
+            mesh: TriangleMesh::from_data(builder.finish(), material).map_err(|error| {
+                ObjError {
+                    path: path.to_path_buf(),
+                    line: None,
+                    message: error.to_string(),
+                    cause: None,
+                }
+            })?,
+        });
+    }
+
+    Ok(ObjModel { groups, materials })
+}
+
+pub fn load_mtl(path: impl AsRef<Path>) -> Result<Vec<MtlMaterial>, ObjError> {
+    let path = path.as_ref();
+    let source = read_file(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let mut parser = LineParser::new(path, line_index + 1, line);
+        let Some(keyword) = parser.next_token() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            let name = parser
+                .rest()
+                .ok_or_else(|| parser.error("missing material name"))?;
+            materials.push(MtlMaterial::new(name));
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            return Err(parser.error(&format!("\"{keyword}\" before any newmtl")));
+        };
+        match keyword {
+            "Kd" => material.diffuse = parser.vector()?,
+            "Ks" => material.specular = parser.vector()?,
+            "Ke" => material.emission = parser.vector()?,
+            "Ns" => material.shininess = parser.number()?,
+            "Ni" => material.refraction_index = parser.number()?,
+            "d" => material.dissolve = parser.number()?,
+            "Tr" => material.dissolve = 1.0 - parser.number()?,
+            "map_Kd" => {
+                // Texture options come before the file name, which is always last.
+                let name = parser
+                    .rest()
+                    .and_then(|rest| rest.split_whitespace().last())
+                    .ok_or_else(|| parser.error("missing texture file name"))?;
+                material.diffuse_map = Some(directory.join(name));
+            }
+            // Statements for features the renderer does not model.
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+fn read_file(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|err| ObjError {
+        path: path.to_path_buf(),
+        line: None,
+        message: err.to_string(),
+        cause: None,
+    })
+}
+
+fn max_component(colour: Colour) -> f64 {
+    colour.x.max(colour.y).max(colour.z)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct FaceCorner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+// Collects the faces of one group, turning OBJ's separate position, uv and normal
+// indices into a single index per unique combination.
+struct GroupBuilder {
+    name: String,
+    material: Option<String>,
+    data: MeshData,
+    normals: Vec<Option<Vector>>,
+    uvs: Vec<Option<(f64, f64)>>,
+    vertex_lookup: HashMap<FaceCorner, usize>,
+}
+
+impl GroupBuilder {
+    fn new(name: &str, material: &Option<String>) -> Self {
+        GroupBuilder {
+            name: name.to_string(),
+            material: material.clone(),
+            data: MeshData {
+                positions: Vec::new(),
+                normals: None,
+                uvs: None,
+                indices: Vec::new(),
+            },
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            vertex_lookup: HashMap::new(),
+        }
+    }
+
+    // Polygons are triangulated as a fan around their first vertex.
+    fn add_polygon(
+        &mut self,
+        corners: &[FaceCorner],
+        positions: &[Point],
+        uvs: &[(f64, f64)],
+        normals: &[Vector],
+    ) {
+        let indices: Vec<usize> = corners
+            .iter()
+            .map(|&corner| self.vertex(corner, positions, uvs, normals))
+            .collect();
+        for i in 1..indices.len() - 1 {
+            self.data
+                .indices
+                .push([indices[0], indices[i], indices[i + 1]]);
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        corner: FaceCorner,
+        positions: &[Point],
+        uvs: &[(f64, f64)],
+        normals: &[Vector],
+    ) -> usize {
+        if let Some(&index) = self.vertex_lookup.get(&corner) {
+            return index;
+        }
+        let index = self.data.positions.len();
+        self.data.positions.push(positions[corner.position]);
+        self.uvs.push(corner.uv.map(|uv| uvs[uv]));
+        self.normals
+            .push(corner.normal.map(|normal| normals[normal]));
+        self.vertex_lookup.insert(corner, index);
+        index
+    }
+
+    // Normals and uvs are only kept if every vertex of the group has one.
+    fn finish(mut self) -> MeshData {
+        self.data.normals = self.normals.into_iter().collect();
+        self.data.uvs = self.uvs.into_iter().collect();
+        self.data
+    }
+}
+
+struct LineParser<'a> {
+    path: &'a Path,
+    line: usize,
+    rest: &'a str,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(path: &'a Path, line: usize, text: &'a str) -> Self {
+        let text = match text.find('#') {
+            Some(comment) => &text[..comment],
+            None => text,
+        };
+        LineParser {
+            path,
+            line,
+            rest: text.trim(),
+        }
+    }
+
+    fn error(&self, message: &str) -> ObjError {
+        ObjError {
+            path: self.path.to_path_buf(),
+            line: Some(self.line),
+            message: message.to_string(),
+            cause: None,
+        }
+    }
+
+    fn next_token(&mut self) -> Option<&'a str> {
+        let trimmed = self.rest.trim_start();
+        if trimmed.is_empty() {
+            return None;
+        }
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        self.rest = &trimmed[end..];
+        Some(&trimmed[..end])
+    }
+
+    // The remainder of the line, for statements whose argument may contain spaces.
+    fn rest(&mut self) -> Option<&'a str> {
+        let rest = self.rest.trim();
+        self.rest = "";
+        (!rest.is_empty()).then_some(rest)
+    }
+
+    fn optional_number(&mut self) -> Result<Option<f64>, ObjError> {
+        match self.next_token() {
+            Some(token) => token
+                .parse()
+                .map(Some)
+                .map_err(|_| self.error(&format!("invalid number \"{token}\""))),
+            None => Ok(None),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, ObjError> {
+        self.optional_number()?
+            .ok_or_else(|| self.error("expected a number"))
+    }
+
+    fn vector(&mut self) -> Result<Vector, ObjError> {
+        Ok(Vector::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    // Parses "v", "v/vt", "v//vn" or "v/vt/vn", resolving negative (relative) indices.
+    fn face_corner(
+        &self,
+        token: &str,
+        position_count: usize,
+        uv_count: usize,
+        normal_count: usize,
+    ) -> Result<FaceCorner, ObjError> {
+        let mut parts = token.split('/');
+        let position = parts.next().unwrap_or("");
+        let uv = parts.next().filter(|part| !part.is_empty());
+        let normal = parts.next().filter(|part| !part.is_empty());
+
+        Ok(FaceCorner {
+            position: self.resolve_index(position, position_count, "vertex")?,
+            uv: uv
+                .map(|uv| self.resolve_index(uv, uv_count, "texture coordinate"))
+                .transpose()?,
+            normal: normal
+                .map(|normal| self.resolve_index(normal, normal_count, "normal"))
+                .transpose()?,
+        })
+    }
+
+    fn resolve_index(&self, token: &str, count: usize, kind: &str) -> Result<usize, ObjError> {
+        let index: i64 = token
+            .parse()
+            .map_err(|_| self.error(&format!("invalid {kind} index \"{token}\"")))?;
+        let resolved = match index {
+            index if index > 0 => index - 1,
+            index if index < 0 => count as i64 + index,
+            _ => -1,
+        };
+        match resolved >= 0 && (resolved as usize) < count {
+            true => Ok(resolved as usize),
+            false => Err(self.error(&format!(
+                "{kind} index {index} out of range, {count} defined so far"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory of files for one test, so tests running at the same time do not see
+    // each other's files. It is removed again when dropped.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_files(test: &str, files: &[(&str, &str)]) -> TestDir {
+        let directory =
+            std::env::temp_dir().join(format!("ray-tracing-obj-{}-{test}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let directory = TestDir(directory);
+        for (name, contents) in files {
+            fs::write(directory.join(name), contents).unwrap();
+        }
+        directory
+    }
+
+    fn error(test: &str, obj: &str) -> String {
+        let directory = write_files(test, &[("model.obj", obj)]);
+        match load_obj(directory.join("model.obj")) {
+            Ok(_) => panic!("expected the model to be rejected"),
+            Err(err) => format!("{}: {}", err.line.unwrap_or(0), err.message),
+        }
+    }
+
+    // The corners of every triangle of every group, in order.
+    fn triangles(model: &ObjModel) -> Vec<[Point; 3]> {
+        model
+            .groups
+            .iter()
+            .flat_map(|group| {
+                let data = group.mesh.data();
+                data.indices
+                    .iter()
+                    .map(|&[a, b, c]| [data.positions[a], data.positions[b], data.positions[c]])
+            })
+            .collect()
+    }
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn malformed_faces_are_reported_with_their_line() {
+        assert_eq!(
+            error("two_corners", &format!("{SQUARE}f 1 2\n")),
+            "5: face needs at least three vertices"
+        );
+        assert_eq!(
+            error("bad_index", &format!("{SQUARE}\nf 1 2 three\n")),
+            "6: invalid vertex index \"three\""
+        );
+        assert_eq!(
+            error("out_of_range", &format!("{SQUARE}f 1 2 5\n")),
+            "5: vertex index 5 out of range, 4 defined so far"
+        );
+        assert_eq!(
+            error("zero", &format!("{SQUARE}f 0 1 2\n")),
+            "5: vertex index 0 out of range, 4 defined so far"
+        );
+        assert_eq!(
+            error(
+                "missing_normal",
+                &format!("{SQUARE}vn 0 0 1\nf 1//1 2//2 3//1\n")
+            ),
+            "6: normal index 2 out of range, 1 defined so far"
+        );
+        assert_eq!(
+            error("bad_vertex", "v 0 0 zero\n"),
+            "1: invalid number \"zero\""
+        );
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_vertex() {
+        let directory = write_files(
+            "negative",
+            &[
+                ("absolute.obj", &format!("{SQUARE}f 1 2 3 4\n")),
+                ("relative.obj", &format!("{SQUARE}f -4 -3 -2 -1\n")),
+            ],
+        );
+        let absolute = load_obj(directory.join("absolute.obj")).unwrap();
+        let relative = load_obj(directory.join("relative.obj")).unwrap();
+        assert_eq!(relative.triangle_count(), 2);
+        assert_eq!(triangles(&relative), triangles(&absolute));
+
+        assert_eq!(
+            error("before_first", &format!("{SQUARE}f -5 -1 -2\n")),
+            "5: vertex index -5 out of range, 4 defined so far"
+        );
+    }
+
+    #[test]
+    fn material_libraries_are_loaded_beside_the_model() {
+        let directory = write_files(
+            "mtllib",
+            &[
+                (
+                    "model.obj",
+                    &format!("mtllib red.mtl\n{SQUARE}usemtl red\nf 1 2 3\n"),
+                ),
+                ("red.mtl", "newmtl red\nKd 0.8 0.1 0.1\n"),
+            ],
+        );
+        let model = load_obj(directory.join("model.obj")).unwrap();
+        assert_eq!(model.groups[0].material.as_deref(), Some("red"));
+        assert_eq!(model.materials["red"].diffuse, Colour::new(0.8, 0.1, 0.1));
+    }
+
+    #[test]
+    fn one_statement_may_name_several_libraries() {
+        let directory = write_files(
+            "several_mtllibs",
+            &[
+                (
+                    "model.obj",
+                    &format!(
+                        "mtllib red.mtl blue.mtl\n{SQUARE}usemtl red\nf 1 2 3\nusemtl blue\nf 1 3 4\n"
+                    ),
+                ),
+                ("red.mtl", "newmtl red\nKd 0.8 0.1 0.1\n"),
+                ("blue.mtl", "newmtl blue\nKd 0.1 0.1 0.8\n"),
+            ],
+        );
+        let model = load_obj(directory.join("model.obj")).unwrap();
+        assert_eq!(model.groups.len(), 2);
+        assert_eq!(model.materials["red"].diffuse, Colour::new(0.8, 0.1, 0.1));
+        assert_eq!(model.materials["blue"].diffuse, Colour::new(0.1, 0.1, 0.8));
+    }
+
+    #[test]
+    fn missing_materials_are_reported() {
+        let directory = write_files(
+            "missing_mtl",
+            &[(
+                "model.obj",
+                &format!("mtllib missing.mtl\n{SQUARE}f 1 2 3\n"),
+            )],
+        );
+        let err = match load_obj(directory.join("model.obj")) {
+            Ok(_) => panic!("expected the missing library to be reported"),
+            Err(err) => err,
+        };
+        assert_eq!(err.path, directory.join("model.obj"));
+        assert_eq!(err.line, Some(1));
+        let cause = err.cause.expect("the library's own error should be kept");
+        assert_eq!(cause.path, directory.join("missing.mtl"));
+        assert_eq!(cause.line, None);
+
+        let directory = write_files(
+            "broken_mtl",
+            &[
+                (
+                    "model.obj",
+                    &format!("{SQUARE}mtllib broken.mtl\nf 1 2 3\n"),
+                ),
+                ("broken.mtl", "newmtl red\nKd 0.8 red 0.1\n"),
+            ],
+        );
+        let err = match load_obj(directory.join("model.obj")) {
+            Ok(_) => panic!("expected the broken library to be reported"),
+            Err(err) => err,
+        };
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}:5: could not load material library \"broken.mtl\": {}:2: invalid number \"red\"",
+                directory.join("model.obj").display(),
+                directory.join("broken.mtl").display()
+            )
+        );
+
+        assert_eq!(
+            error(
+                "unknown_material",
+                &format!("{SQUARE}usemtl gold\nf 1 2 3\n")
+            ),
+            "5: unknown material \"gold\""
+        );
+    }
+}
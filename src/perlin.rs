@@ -0,0 +1,114 @@
+use crate::{
+    rng::{random, random_range},
+    vector::{Point, Vector, dot},
+};
+
+const POINT_COUNT: usize = 256;
+
+// Gradient noise on a lattice of random unit vectors, smoothly interpolated between
+// lattice points.
+pub struct Perlin {
+    gradients: Vec<Vector>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Vector::new(
+                    random_range(-1.0..1.0),
+                    random_range(-1.0..1.0),
+                    random_range(-1.0..1.0),
+                )
+                .normalize()
+            })
+            .collect();
+
+        Perlin {
+            gradients,
+            perm_x: generate_permutation(),
+            perm_y: generate_permutation(),
+            perm_z: generate_permutation(),
+        }
+    }
+
+    // Returns a value in roughly [-1, 1].
+    pub fn noise(&self, point: Point) -> f64 {
+        let u = point.x - point.x.floor();
+        let v = point.y - point.y.floor();
+        let w = point.z - point.z.floor();
+
+        let i = point.x.floor() as i64;
+        let j = point.y.floor() as i64;
+        let k = point.z.floor() as i64;
+
+        let mut corners = [[[Vector::new(0.0, 0.0, 0.0); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize];
+                    *corner = self.gradients[index];
+                }
+            }
+        }
+
+        interpolate(&corners, u, v, w)
+    }
+
+    // Sums several octaves of noise, each at double the frequency and half the weight.
+    pub fn turbulence(&self, point: Point, depth: u32) -> f64 {
+        let mut accumulated = 0.0;
+        let mut temp_point = point;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accumulated += weight * self.noise(temp_point);
+            weight *= 0.5;
+            temp_point *= 2.0;
+        }
+
+        accumulated.abs()
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn generate_permutation() -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..POINT_COUNT).collect();
+    for i in (1..POINT_COUNT).rev() {
+        let target = (random::<f64>() * (i + 1) as f64) as usize;
+        permutation.swap(i, target.min(i));
+    }
+    permutation
+}
+
+fn interpolate(corners: &[[[Vector; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    // Hermite smoothing removes the grid artifacts of plain trilinear interpolation.
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+
+    let mut accumulated = 0.0;
+    for (i, plane) in corners.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = Vector::new(u - fi, v - fj, w - fk);
+                accumulated += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * dot(*gradient, weight);
+            }
+        }
+    }
+    accumulated
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vector::{Onb, Point, Vector, dot},
+};
+
+// An infinite plane through point, facing along normal. It has no bounding box, so
+// the world keeps it out of the BVH.
+pub struct Plane {
+    pub point: Point,
+    pub normal: Vector,
+    pub material: Arc<Box<dyn Material + Sync + Send>>,
+    // Axes in the plane for texture coordinates.
+    onb: Onb,
+}
+
+impl Plane {
+    pub fn new(
+        point: Point,
+        normal: Vector,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        let normal = normal.normalize();
+        Self {
+            point,
+            normal,
+            material,
+            onb: Onb::new(normal),
+        }
+    }
+}
+
+impl Hittable for Plane {
+    // The texture coordinates are distances along the plane from point, so image
+    // textures repeat every unit.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let t = intersect_plane(ray, ray_t, self.point, self.normal)?;
+        let point = ray.at(t);
+        let offset = point - self.point;
+        let front_face = dot(ray.direction, self.normal) < 0.0;
+
+        Some(HitRecord {
+            point,
+            normal: match front_face {
+                true => self.normal,
+                false => -self.normal,
+            },
+            t,
+            u: dot(offset, self.onb.u),
+            v: dot(offset, self.onb.v),
+            front_face,
+            material: self.material.clone(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+}
+
+// The distance along the ray to the plane through point with the given normal, if
+// it lies within ray_t.
+pub(crate) fn intersect_plane(
+    ray: &Ray,
+    ray_t: Interval,
+    point: Point,
+    normal: Vector,
+) -> Option<f64> {
+    let denominator = dot(normal, ray.direction);
+    if denominator.abs() < 1e-12 {
+        return None;
+    }
+
+    let t = dot(normal, point - ray.origin) / denominator;
+    match ray_t.surounds(t) {
+        true => Some(t),
+        false => None,
+    }
+}
@@ -0,0 +1,156 @@
+// Real roots of polynomials, for intersecting rays with curved surfaces. Roots are
+// returned in increasing order.
+
+// Roots of a x² + b x + c, falling back to the linear equation when a is zero.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return match b.abs() < 1e-12 {
+            true => Vec::new(),
+            false => vec![-c / b],
+        };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+    // Avoids cancellation between b and the square root.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = match q == 0.0 {
+        true => (0.0, 0.0),
+        false => (q / a, c / q),
+    };
+    match t0 <= t1 {
+        true => vec![t0, t1],
+        false => vec![t1, t0],
+    }
+}
+
+// The largest real root of x³ + a x² + b x + c, which always has at least one.
+pub fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    // Substituting x = y - a / 3 gives y³ + p y + q.
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let shift = -a / 3.0;
+
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        return (-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() + shift;
+    }
+    // Three real roots, found with the trigonometric method.
+    let r = (-p / 3.0).max(0.0).sqrt();
+    if r == 0.0 {
+        return shift;
+    }
+    let phi = (-q / (2.0 * r * r * r)).clamp(-1.0, 1.0).acos();
+    2.0 * r * (phi / 3.0).cos() + shift
+}
+
+// Roots of x⁴ + a x³ + b x² + c x + d by Ferrari's method, each polished with a few
+// steps of Newton's method since the closed form loses precision.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Substituting x = y - a / 4 gives y⁴ + p y² + q y + r.
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // A quadratic in y².
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                roots.push(z.sqrt());
+                roots.push(-z.sqrt());
+            }
+        }
+    } else {
+        // The quartic is the difference of two squares, (y² + p / 2 + m)² and
+        // (√(2m) y - q / (2√(2m)))², for a positive root m of the resolvent cubic.
+        let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m <= 0.0 {
+            return Vec::new();
+        }
+        let s = (2.0 * m).sqrt();
+        roots.extend(solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s)));
+        roots.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+    }
+
+    let polynomial = |x: f64| (((x + a) * x + b) * x + c) * x + d;
+    let derivative = |x: f64| ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+    for root in roots.iter_mut() {
+        *root -= a / 4.0;
+        for _ in 0..3 {
+            let slope = derivative(*root);
+            if slope.abs() < 1e-12 {
+                break;
+            }
+            *root -= polynomial(*root) / slope;
+        }
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(found: Vec<f64>, expected: &[f64]) {
+        assert_eq!(found.len(), expected.len(), "{found:?}");
+        for (root, expected) in found.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-6, "{found:?}");
+        }
+    }
+
+    #[test]
+    fn quadratics() {
+        assert_roots(solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(2.0, 0.0, -8.0), &[-2.0, 2.0]);
+        assert_roots(solve_quadratic(0.0, 2.0, -1.0), &[0.5]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+    }
+
+    #[test]
+    fn cubics() {
+        // (x - 1)(x - 2)(x - 3) and (x - 2)(x² + 1).
+        assert!((largest_cubic_root(-6.0, 11.0, -6.0) - 3.0).abs() < 1e-9);
+        assert!((largest_cubic_root(-2.0, 1.0, -2.0) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quartics() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            solve_quartic(-10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (x² - 1)(x² - 4), which has no odd terms.
+        assert_roots(solve_quartic(0.0, -5.0, 0.0, 4.0), &[-2.0, -1.0, 1.0, 2.0]);
+        // (x + 3)(x - 0.5)(x² + 1)
+        assert_roots(solve_quartic(2.5, -0.5, 2.5, -1.5), &[-3.0, 0.5]);
+        assert_roots(solve_quartic(0.0, 0.0, 0.0, 1.0), &[]);
+    }
+
+    // Rays from far away give roots far from zero, where the closed form alone is
+    // badly off.
+    #[test]
+    fn distant_quartic_roots_are_polished() {
+        // (x - 100)(x - 101)(x - 103)(x - 104)
+        let roots = [100.0, 101.0, 103.0, 104.0];
+        let a = -roots.iter().sum::<f64>();
+        let b = 100.0 * 101.0
+            + 100.0 * 103.0
+            + 100.0 * 104.0
+            + 101.0 * 103.0
+            + 101.0 * 104.0
+            + 103.0 * 104.0;
+        let c = -(100.0 * 101.0 * 103.0
+            + 100.0 * 101.0 * 104.0
+            + 100.0 * 103.0 * 104.0
+            + 101.0 * 103.0 * 104.0);
+        let d = roots.iter().product::<f64>();
+        assert_roots(solve_quartic(a, b, c, d), &roots);
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, area_to_solid_angle_pdf},
+    interval::Interval,
+    material::Material,
+    plane::intersect_plane,
+    ray::Ray,
+    rng::random,
+    vector::{Point, Vector, dot},
+};
+
+// A parallelogram with one corner at corner and edges u and v leading away from it.
+// The front face is the side u × v points to, and the texture coordinates run from
+// 0 to 1 along each edge.
+pub struct Quad {
+    pub corner: Point,
+    pub u: Vector,
+    pub v: Vector,
+    pub material: Arc<Box<dyn Material + Sync + Send>>,
+    normal: Vector,
+    // Turns an offset in the plane into coordinates along u and v.
+    w: Vector,
+    area: f64,
+}
+
+impl Quad {
+    pub fn new(
+        corner: Point,
+        u: Vector,
+        v: Vector,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        let n = u.cross(v);
+        Self {
+            corner,
+            u,
+            v,
+            material,
+            normal: n.normalize(),
+            w: n / n.magnitude2(),
+            area: n.magnitude(),
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let t = intersect_plane(ray, ray_t, self.corner, self.normal)?;
+        let point = ray.at(t);
+        let offset = point - self.corner;
+        let alpha = dot(self.w, offset.cross(self.v));
+        let beta = dot(self.w, self.u.cross(offset));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let front_face = dot(ray.direction, self.normal) < 0.0;
+        Some(HitRecord {
+            point,
+            normal: match front_face {
+                true => self.normal,
+                false => -self.normal,
+            },
+            t,
+            u: alpha,
+            v: beta,
+            front_face,
+            material: self.material.clone(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::enclosing(
+            &Aabb::from_points(self.corner, self.corner + self.u + self.v),
+            &Aabb::from_points(self.corner + self.u, self.corner + self.v),
+        )
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        match self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) {
+            Some(rec) => area_to_solid_angle_pdf(1.0 / self.area, &rec, direction),
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: Point) -> Vector {
+        self.corner + random::<f64>() * self.u + random::<f64>() * self.v - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    // Two units along x and one along y, facing +z.
+    fn quad() -> Quad {
+        Quad::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)))),
+        )
+    }
+
+    fn hit_from_above(x: f64, y: f64) -> Option<HitRecord> {
+        let ray = Ray::new(Point::new(x, y, 1.0), Vector::new(0.0, 0.0, -1.0));
+        quad().hit(&ray, Interval::new(0.001, f64::INFINITY))
+    }
+
+    #[test]
+    fn edges_and_corners_are_part_of_the_quad() {
+        for (x, y) in [(0.0, 0.0), (2.0, 1.0), (1.0, 0.0), (2.0, 0.5)] {
+            assert!(hit_from_above(x, y).is_some(), "missed ({x}, {y})");
+        }
+        for (x, y) in [(-0.001, 0.5), (2.001, 0.5), (1.0, -0.001), (1.0, 1.001)] {
+            assert!(hit_from_above(x, y).is_none(), "hit ({x}, {y})");
+        }
+
+        let rec = hit_from_above(0.5, 0.25).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-12);
+        assert!((rec.u - 0.25).abs() < 1e-12);
+        assert!((rec.v - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn normals_face_the_ray() {
+        let front = hit_from_above(1.0, 0.5).unwrap();
+        assert!(front.front_face);
+        assert_eq!(front.normal, Vector::new(0.0, 0.0, 1.0));
+
+        let ray = Ray::new(Point::new(1.0, 0.5, -1.0), Vector::new(0.0, 0.0, 1.0));
+        let back = quad()
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert!(!back.front_face);
+        assert_eq!(back.normal, Vector::new(0.0, 0.0, -1.0));
+    }
+}
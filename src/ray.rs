@@ -3,6 +3,8 @@ use crate::vector::*;
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    // When during the exposure the ray was cast, for motion blur.
+    pub time: f64,
 }
 
 impl Ray {
@@ -11,6 +13,14 @@ impl Ray {
     }
 
     pub fn new(origin: Point, direction: Vector) -> Self {
-        Ray { origin, direction }
+        Ray::with_time(origin, direction, 0.0)
+    }
+
+    pub fn with_time(origin: Point, direction: Vector, time: f64) -> Self {
+        Ray {
+            origin,
+            direction,
+            time,
+        }
     }
 }
@@ -0,0 +1,32 @@
+use std::cell::RefCell;
+
+use rand::{
+    Rng, SeedableRng,
+    distr::{Distribution, StandardUniform, uniform::SampleRange, uniform::SampleUniform},
+    rngs::StdRng,
+};
+
+// Every random number used while building or rendering a scene comes from this
+// per-thread generator, so a render can be made reproducible by seeding it.
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_os_rng());
+}
+
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+pub fn random<T>() -> T
+where
+    StandardUniform: Distribution<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().random())
+}
+
+pub fn random_range<T, R>(range: R) -> T
+where
+    T: SampleUniform,
+    R: SampleRange<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().random_range(range))
+}
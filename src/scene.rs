@@ -0,0 +1,1645 @@
+//! Loads scenes from JSON files so they can be changed without recompiling.
+//!
+//! A scene file is a JSON object with five sections, all optional:
+//!
+//! ```json
+//! {
+//!     "camera": {
+//!         "aspect_ratio": 1.7778,
+//!         "image_width": 1280,
+//!         "samples_per_pixel": 500,
+//!         "max_depth": 50,
+//!         "vfov": 20.0,
+//!         "lookfrom": [13.0, 2.0, 3.0],
+//!         "lookat": [0.0, 0.0, 0.0],
+//!         "defocus_angle": 0.6,
+//!         "focus_dist": 10.0,
+//!         "russian_roulette_depth": 5,
+//!         "integrator": "path",
+//!         "shutter_open": 0.0,
+//!         "shutter_close": 1.0
+//!     },
+//!     "environment": { "type": "gradient", "bottom": [1.0, 1.0, 1.0], "top": [0.5, 0.7, 1.0] },
+//!     "fog": { "density": 0.05, "colour": [0.8, 0.8, 0.8], "height": 1.0 },
+//!     "materials": {
+//!         "ground": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] },
+//!         "steel": { "type": "metal", "albedo": [0.7, 0.6, 0.5], "roughness": 0.1 },
+//!         "glass": { "type": "dielectric", "refraction_index": 1.5 },
+//!         "lamp": { "type": "light", "colour": [1.0, 0.9, 0.4] }
+//!     },
+//!     "objects": [
+//!         { "type": "sphere", "centre": [0.0, 1.0, 0.0], "radius": 1.0, "material": "steel" },
+//!         { "type": "triangle", "vertices": [[0, 0, 0], [1, 0, 0], [0, 1, 0]], "material": "ground" },
+//!         { "type": "mesh", "positions": [[0, 0, 0], [1, 0, 0], [0, 1, 0]], "indices": [[0, 1, 2]],
+//!           "normals": [[0, 0, 1], [0, 0, 1], [0, 0, 1]], "material": "ground" },
+//!         { "type": "obj", "file": "models/teapot.obj", "material": "steel" },
+//!         { "type": "quad", "corner": [-1, 3, -1], "u": [2, 0, 0], "v": [0, 0, 2], "material": "lamp" },
+//!         { "type": "plane", "point": [0, 0, 0], "normal": [0, 1, 0], "material": "ground" },
+//!         { "type": "disk", "centre": [3, 0.01, 0], "normal": [0, 1, 0], "radius": 0.5, "material": "steel" },
+//!         { "type": "box", "min": [-3, 0, -1], "max": [-2, 1, 0], "material": "ground" },
+//!         { "type": "cylinder", "base": [2, 0, 2], "top": [2, 1, 2], "radius": 0.3, "material": "steel" },
+//!         { "type": "cone", "base": [-2, 0, 2], "top": [-2, 1, 2], "base_radius": 0.4, "material": "steel" },
+//!         { "type": "capsule", "base": [0, 0.3, 3], "top": [1, 0.3, 3], "radius": 0.3, "material": "steel" },
+//!         { "type": "torus", "centre": [0, 0.2, -2], "major_radius": 0.6, "minor_radius": 0.2, "material": "steel" },
+//!         { "type": "sdf", "shape": { "type": "sphere", "centre": [3, 1, -3], "radius": 1.0 }, "material": "glass" }
+//!     ]
+//! }
+//! ```
+//!
+//! Camera fields that are left out take the values of `CameraSettings::default()`.
+//! Vectors and colours are arrays of three numbers. The `normals` of a mesh are
+//! optional, and the `material` of an `obj` object is optional and only applies to
+//! faces the file does not assign a material to. Relative `file` paths are resolved
+//! against the directory containing the scene file.
+//!
+//! A `quad` is a parallelogram with edges `u` and `v` leading from `corner`, facing
+//! the way `u` × `v` points. A `plane` is infinite, and a `box` is aligned with the
+//! axes between its `min` and `max` corners; rotate it with a `transform`.
+//!
+//! Cylinders, cones and capsules run from `base` to `top`. A cone's `top_radius`
+//! defaults to 0.0 for a pointed cone, and cylinders and cones have flat ends unless
+//! `caps` is false. A torus is a tube of `minor_radius` around a circle of
+//! `major_radius` about its `axis` (default +y).
+//!
+//! An `sdf` object is drawn by ray marching the signed distance function given as its
+//! `shape`, which may be a `sphere` (`centre`, `radius`), a `box` (`centre`, `size`
+//! and an optional `corner_radius` to round it off), a `torus` around the y axis
+//! (`centre`, `major_radius`, `minor_radius`), or a `union`, `subtraction` or
+//! `intersection` of two shapes `a` and `b`, blended over an optional `smoothness`:
+//!
+//! ```json
+//! { "type": "subtraction", "smoothness": 0.1,
+//!   "a": { "type": "box", "centre": [0, 1, 0], "size": [2, 2, 2], "corner_radius": 0.2 },
+//!   "b": { "type": "sphere", "centre": [0, 1, 0], "radius": 1.2 } }
+//! ```
+//!
+//! A subtraction carves `b` out of `a`.
+//!
+//! A `csg` object combines two solid objects `a` and `b`, written out in full, by
+//! an `operation` of `union`, `intersection` or `difference` (`a` with `b` cut
+//! out). Spheres, boxes, capsules, tori, SDF shapes, cylinders and cones with caps,
+//! and other `csg` objects are solid. Each part may have its own transform:
+//!
+//! ```json
+//! { "type": "csg", "operation": "difference",
+//!   "a": { "type": "box", "min": [-1, 0, -1], "max": [1, 2, 1], "material": "steel" },
+//!   "b": { "type": "cylinder", "base": [0, -1, 0], "top": [0, 3, 0], "radius": 0.5, "material": "steel" } }
+//! ```
+//!
+//! A `medium` fills a solid `boundary`, written out in full like a CSG part, with
+//! smoke or mist of the given `density` that scatters light equally in every
+//! direction. Its `albedo`, a colour or texture, is the fraction of the light
+//! scattered rather than absorbed:
+//!
+//! ```json
+//! { "type": "medium", "density": 2.0, "albedo": [0.9, 0.9, 0.9],
+//!   "boundary": { "type": "sphere", "centre": [0, 1, 0], "radius": 1.0, "material": "glass" } }
+//! ```
+//!
+//! A `volume` is a medium whose density varies, given by a grid of voxels filling the
+//! box between `min` and `max`. The grid is read from a `file`, either a text file
+//! holding the resolution as three whole numbers followed by a density for every
+//! voxel, or, when a `resolution` is given, a raw file of little endian 32 bit
+//! floats. Densities run through x fastest, then y, then z. Grids may have at most
+//! 2048 voxels along each axis and 2²⁷ in all. Alternatively the grid is generated
+//! from `noise` as a cloud:
+//!
+//! ```json
+//! { "type": "volume", "min": [-3, 0, -2], "max": [3, 3, 2],
+//!   "noise": { "resolution": [96, 48, 64], "scale": 1.2 },
+//!   "density": 20.0, "albedo": [0.95, 0.95, 0.95], "anisotropy": 0.6 }
+//! ```
+//!
+//! The grid's densities are multiplied by `density` (default 1.0), and the noise
+//! `scale` sets how fine its detail is (default 1.0). As for a medium, the `albedo`
+//! may be a colour or a texture. A volume's `anisotropy`, between
+//! -1 and 1, makes light scatter mostly forwards when positive, as in clouds, or
+//! backwards when negative (default 0.0, the same in every direction).
+//!
+//! The `fog` fills the whole scene in the same way, or only the space below its
+//! `height` if one is given. Fog without a height hides the environment, which then
+//! no longer lights the scene.
+//!
+//! A `light` may also have an `intensity` scaling its colour (default 1.0),
+//! `two_sided` set to false to only emit from the front of the surface, and a
+//! `surface` material, written out in full, that scatters the light hitting it.
+//!
+//! Spheres, triangles, meshes, `obj` models, quads, disks and boxes made of a `light`
+//! are sampled directly: at every diffuse bounce a shadow ray is sent towards one of
+//! them. Emissive planes, cylinders, cones, capsules, tori, SDF shapes, CSG objects,
+//! moving objects and objects that are not uniformly scaled get no such next event
+//! estimation. They still light the scene, but only through rays that happen to hit
+//! them, so they take many more samples to render without noise.
+//!
+//! A `metal` has a `roughness` from 0.0 (a mirror, the default) to 1.0, which older
+//! scenes call `fuzz`. Its colour
+//! head on is given by an `albedo`, or it is a real metal, either a `preset` of
+//! `gold`, `copper`, `aluminium` or `silver`, or given by the `eta` and `k` of its
+//! complex refractive index at red, green and blue:
+//!
+//! ```json
+//! { "type": "metal", "preset": "gold", "roughness": 0.3 }
+//! { "type": "metal", "eta": [0.2, 0.92, 1.1], "k": [3.91, 2.45, 2.14], "roughness": 0.3 }
+//! ```
+//!
+//! The `albedo` of a `lambertian` or `metal` material may also be a texture:
+//!
+//! ```json
+//! { "type": "checker", "scale": 0.5, "even": [0.2, 0.3, 0.1], "odd": [0.9, 0.9, 0.9] }
+//! { "type": "image", "file": "textures/earth.jpg", "wrap": "repeat" }
+//! { "type": "noise", "style": "marble", "scale": 4.0, "colour": [1.0, 1.0, 1.0] }
+//! ```
+//!
+//! The `even` and `odd` textures of a checker may themselves be colours or textures.
+//! An image's `wrap` is one of `repeat` (the default), `mirror` or `clamp`, and a
+//! noise texture's `style` is one of `smooth`, `turbulence` or `marble` (the default).
+//!
+//! Any object may be placed with a `transform`, a list of steps applied in order:
+//!
+//! ```json
+//! "transform": [
+//!     { "scale": 2.0 },
+//!     { "rotate": { "axis": [0.0, 1.0, 0.0], "degrees": 45.0 } },
+//!     { "translate": [1.0, 0.0, -2.0] },
+//!     { "look_at": { "from": [0.0, 1.0, 0.0], "to": [0.0, 1.0, -5.0], "up": [0.0, 1.0, 0.0] } }
+//! ]
+//! ```
+//!
+//! A `scale` is a number or a vector of factors for each axis, and `look_at` places
+//! the object at `from` with its +z axis towards `to` and its +y axis as close to
+//! `up` as it can be (`up` defaults to +y, and must not point along the +z axis).
+//! Instead of a transform an object may have `instances`, a list of transforms, to
+//! place many copies that share one copy of the geometry.
+//!
+//! Any object may also have a `motion`, the distance it moves between time 0 and time 1.
+//! Instead of a transform it may be `moving`, from one transform at time 0 to
+//! another at time 1:
+//!
+//! ```json
+//! "moving": {
+//!     "start": [{ "translate": [0.0, 1.0, 0.0] }],
+//!     "end": [
+//!         { "rotate": { "axis": [0.0, 1.0, 0.0], "degrees": 90.0 } },
+//!         { "translate": [0.5, 1.0, 0.0] }
+//!     ]
+//! }
+//! ```
+//!
+//! In between, the object turns steadily from one rotation to the other while its
+//! position and scale change linearly, and outside those times it stays at either
+//! end. Rays are cast at times between the camera's `shutter_open` and
+//! `shutter_close` (both default to 0.0), so objects that move while the shutter is
+//! open are blurred.
+//!
+//! The `environment` is what rays that miss every object see, and lights the scene.
+//! It may be a colour, which is the same as a `constant` environment, or one of:
+//!
+//! ```json
+//! { "type": "constant", "colour": [0.1, 0.1, 0.1] }
+//! { "type": "gradient", "bottom": [1.0, 1.0, 1.0], "top": [0.5, 0.7, 1.0] }
+//! { "type": "image", "file": "skies/park.hdr", "rotation": 90.0, "intensity": 1.5 }
+//! { "type": "sky", "sun_direction": [1.0, 0.5, -0.3], "turbidity": 3.0, "ground_albedo": [0.3, 0.3, 0.3] }
+//! ```
+//!
+//! An `image` environment is an equirectangular map, with `rotation` in degrees
+//! about the vertical axis (default 0.0) and `intensity` scaling it (default 1.0).
+//! A `sky` is a physically based daylight sky with a sun disk. Its `turbidity` runs
+//! from about 2 for clear air to 10 for haze (default 3.0), `ground_albedo` colours
+//! the ground below the horizon (default 0.3 grey), `sun_angle` is the diameter of
+//! the sun in degrees (default 0.53), and `intensity` is the brightness of a white
+//! surface in full daylight (default 1.0).
+//! Without an environment the scene is lit by a dim blue grey.
+//!
+//! The camera's `integrator` is one of `path` (the default), `direct` or
+//! `ambient_occlusion`. Ambient occlusion may also be given as an object with the
+//! distance beyond which occluders are ignored:
+//!
+//! ```json
+//! { "type": "ambient_occlusion", "distance": 2.0 }
+//! ```
+//!
+//! Problems are reported with the JSON path of the offending value, for example
+//! `objects[12].material: unknown material "gold"`.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    Colour,
+    aabb::Aabb,
+    box_shape::BoxShape,
+    camera::CameraSettings,
+    capsule::Capsule,
+    csg::{Csg, CsgOperation},
+    cylinder::{Cone, Cylinder},
+    density_grid::{DensityGrid, MAX_RESOLUTION, MAX_VOXELS, voxel_count},
+    disk::Disk,
+    environment::{ConstantEnvironment, Environment, EnvironmentMap, GradientSky},
+    hittable::Hittable,
+    hittable_list::HittableList,
+    instance::Instance,
+    integrator::IntegratorKind,
+    material::{Dielectric, Lambertian, Light, Material, Metal},
+    medium::{ConstantMedium, Fog, GridMedium},
+    mesh::TriangleMesh,
+    moving::Moving,
+    obj::{ObjError, load_obj_with_default},
+    plane::Plane,
+    quad::Quad,
+    ray::Ray,
+    sdf::{
+        Sdf, SdfBox, SdfShape, SdfSphere, SdfTorus, SmoothIntersection, SmoothSubtraction,
+        SmoothUnion,
+    },
+    sky::PreethamSky,
+    sphere::{MovingSphere, Sphere},
+    texture::{Checker, ImageTexture, NoiseStyle, NoiseTexture, SolidColour, Texture, WrapMode},
+    torus::Torus,
+    transform::Transform,
+    triangle::Triangle,
+    vector::{Point, Vector},
+    world::World,
+};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io { file: PathBuf, message: String },
+    Json { file: PathBuf, message: String },
+    Invalid { path: String, message: String },
+    Obj { path: String, error: ObjError },
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io { file, message } | SceneError::Json { file, message } => {
+                write!(f, "{}: {}", file.display(), message)
+            }
+            SceneError::Invalid { path, message } => write!(f, "{path}: {message}"),
+            SceneError::Obj { path, error } => write!(f, "{path}: {error}"),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+pub struct Scene {
+    pub camera: CameraSettings,
+    pub world: World,
+}
+
+pub fn load_scene(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|err| SceneError::Io {
+        file: path.to_path_buf(),
+        message: err.to_string(),
+    })?;
+    let json: Value = serde_json::from_str(&source).map_err(|err| SceneError::Json {
+        file: path.to_path_buf(),
+        message: err.to_string(),
+    })?;
+    parse_scene(&json, path.parent().unwrap_or(Path::new("")))
+}
+
+// Builds a scene from already parsed JSON. Files it references are looked up
+// relative to base_dir.
+pub fn parse_scene(json: &Value, base_dir: &Path) -> Result<Scene, SceneError> {
+    let root = Node::root(json);
+    root.expect_fields(&["camera", "environment", "fog", "materials", "objects"])?;
+
+    let camera = match root.get("camera") {
+        Some(node) => parse_camera(&node)?,
+        None => CameraSettings::default(),
+    };
+
+    let mut materials = HashMap::new();
+    if let Some(node) = root.get("materials") {
+        for (name, material) in node.entries()? {
+            materials.insert(name.to_string(), parse_material(&material, base_dir)?);
+        }
+    }
+
+    let mut world = HittableList::new();
+    if let Some(node) = root.get("objects") {
+        for object in node.elements()? {
+            for hittable in parse_object(&object, &materials, base_dir)? {
+                world.add(hittable);
+            }
+        }
+    }
+
+    if let Some(node) = root.get("fog") {
+        world.add(parse_fog(&node)?);
+    }
+
+    let mut world = World::new(world);
+    if let Some(node) = root.get("environment") {
+        world = world.with_environment(parse_environment(&node, base_dir)?);
+    }
+
+    Ok(Scene { camera, world })
+}
+
+fn parse_fog(node: &Node) -> Result<Arc<Box<dyn Hittable>>, SceneError> {
+    node.expect_fields(&["density", "colour", "height"])?;
+    let mut fog = Fog::new(
+        node.field("density")?.positive_number()?,
+        node.field("colour")?.colour()?,
+    );
+    if let Some(height) = node.get("height") {
+        fog = fog.with_height(height.number()?);
+    }
+    Ok(Arc::new(Box::new(fog)))
+}
+
+fn parse_environment(
+    node: &Node,
+    base_dir: &Path,
+) -> Result<Arc<Box<dyn Environment>>, SceneError> {
+    if node.value.is_array() {
+        return Ok(Arc::new(Box::new(ConstantEnvironment::new(node.colour()?))));
+    }
+
+    let environment: Box<dyn Environment> = match node.field("type")?.string()? {
+        "constant" => {
+            node.expect_fields(&["type", "colour"])?;
+            Box::new(ConstantEnvironment::new(node.field("colour")?.colour()?))
+        }
+        "gradient" => {
+            node.expect_fields(&["type", "bottom", "top"])?;
+            Box::new(GradientSky::new(
+                node.field("bottom")?.colour()?,
+                node.field("top")?.colour()?,
+            ))
+        }
+        "image" => {
+            node.expect_fields(&["type", "file", "rotation", "intensity"])?;
+            let file_node = node.field("file")?;
+            let file = base_dir.join(file_node.string()?);
+            let mut map = EnvironmentMap::load(&file)
+                .map_err(|err| file_node.invalid(&format!("{}: {err}", file.display())))?;
+            if let Some(rotation) = node.get("rotation") {
+                map = map.with_rotation(rotation.number()?);
+            }
+            if let Some(intensity) = node.get("intensity") {
+                map = map.with_intensity(intensity.positive_number()?);
+            }
+            Box::new(map)
+        }
+        "sky" => {
+            node.expect_fields(&[
+                "type",
+                "sun_direction",
+                "turbidity",
+                "ground_albedo",
+                "sun_angle",
+                "intensity",
+            ])?;
+            let turbidity = match node.get("turbidity") {
+                Some(turbidity) => turbidity.positive_number()?,
+                None => 3.0,
+            };
+            let ground_albedo = match node.get("ground_albedo") {
+                Some(albedo) => albedo.colour()?,
+                None => Colour::new(0.3, 0.3, 0.3),
+            };
+            let mut sky = PreethamSky::new(
+                node.field("sun_direction")?.non_zero_vector()?,
+                turbidity,
+                ground_albedo,
+            );
+            if let Some(sun_angle) = node.get("sun_angle") {
+                sky = sky.with_sun_angle(sun_angle.positive_number()?);
+            }
+            if let Some(intensity) = node.get("intensity") {
+                sky = sky.with_intensity(intensity.positive_number()?);
+            }
+            Box::new(sky)
+        }
+        other => {
+            return Err(node
+                .child("type")
+                .invalid(&format!("unknown environment type \"{other}\"")));
+        }
+    };
+    Ok(Arc::new(environment))
+}
+
+fn parse_camera(node: &Node) -> Result<CameraSettings, SceneError> {
+    node.expect_fields(&[
+        "aspect_ratio",
+        "image_width",
+        "samples_per_pixel",
+        "max_depth",
+        "vfov",
+        "lookfrom",
+        "lookat",
+        "defocus_angle",
+        "focus_dist",
+        "russian_roulette_depth",
+        "integrator",
+        "shutter_open",
+        "shutter_close",
+    ])?;
+    let mut camera = CameraSettings::default();
+
+    if let Some(value) = node.get("aspect_ratio") {
+        camera.aspect_ratio = value.positive_number()?;
+    }
+    if let Some(value) = node.get("image_width") {
+        camera.image_width = value.positive_integer()?;
+    }
+    if let Some(value) = node.get("samples_per_pixel") {
+        camera.samples_per_pixel = value.positive_integer()?;
+    }
+    if let Some(value) = node.get("max_depth") {
+        camera.max_depth = value.positive_integer()?;
+    }
+    if let Some(value) = node.get("vfov") {
+        camera.vfov = value.positive_number()?;
+    }
+    if let Some(value) = node.get("lookfrom") {
+        camera.lookfrom = value.vector()?;
+    }
+    if let Some(value) = node.get("lookat") {
+        camera.lookat = value.vector()?;
+    }
+    if let Some(value) = node.get("defocus_angle") {
+        camera.defocus_angle = value.number()?;
+    }
+    if let Some(value) = node.get("focus_dist") {
+        camera.focus_dist = value.positive_number()?;
+    }
+    if let Some(value) = node.get("russian_roulette_depth") {
+        camera.russian_roulette_depth = value.non_negative_integer()?;
+    }
+    if let Some(value) = node.get("integrator") {
+        camera.integrator = parse_integrator(&value)?;
+    }
+    if let Some(value) = node.get("shutter_open") {
+        camera.shutter_open = value.number()?;
+    }
+    if let Some(value) = node.get("shutter_close") {
+        camera.shutter_close = value.number()?;
+    }
+    Ok(camera)
+}
+
+fn parse_integrator(node: &Node) -> Result<IntegratorKind, SceneError> {
+    if let Some(name) = node.value.as_str() {
+        return name
+            .parse()
+            .map_err(|message: String| node.invalid(&message));
+    }
+
+    let kind = node.field("type")?;
+    match kind.string()? {
+        "ambient_occlusion" => {
+            node.expect_fields(&["type", "distance"])?;
+            let distance = match node.get("distance") {
+                Some(distance) => distance.positive_number()?,
+                None => f64::INFINITY,
+            };
+            Ok(IntegratorKind::AmbientOcclusion { distance })
+        }
+        name => {
+            node.expect_fields(&["type"])?;
+            name.parse()
+                .map_err(|message: String| kind.invalid(&message))
+        }
+    }
+}
+
+fn parse_material(
+    node: &Node,
+    base_dir: &Path,
+) -> Result<Arc<Box<dyn Material + Sync + Send>>, SceneError> {
+    let material: Box<dyn Material + Sync + Send> = match node.field("type")?.string()? {
+        "lambertian" => {
+            node.expect_fields(&["type", "albedo"])?;
+            Box::new(Lambertian::from_texture(parse_texture(
+                &node.field("albedo")?,
+                base_dir,
+            )?))
+        }
+        "metal" => {
+            node.expect_fields(&["type", "albedo", "preset", "eta", "k", "roughness", "fuzz"])?;
+            // fuzz is the older name for roughness.
+            let roughness = match (node.get("roughness"), node.get("fuzz")) {
+                (Some(_), Some(fuzz)) => {
+                    return Err(fuzz.invalid("a metal cannot have both roughness and fuzz"));
+                }
+                (Some(roughness), None) | (None, Some(roughness)) => {
+                    let value = roughness.number()?;
+                    if !(0.0..=1.0).contains(&value) {
+                        return Err(roughness.invalid("expected a number between 0 and 1"));
+                    }
+                    value
+                }
+                (None, None) => 0.0,
+            };
+            let metal = match (
+                node.get("albedo"),
+                node.get("preset"),
+                node.get("eta"),
+                node.get("k"),
+            ) {
+                (Some(albedo), None, None, None) => {
+                    Metal::from_texture(parse_texture(&albedo, base_dir)?, roughness)
+                }
+                (None, Some(preset), None, None) => match preset.string()? {
+                    "gold" => Metal::gold(roughness),
+                    "copper" => Metal::copper(roughness),
+                    "aluminium" => Metal::aluminium(roughness),
+                    "silver" => Metal::silver(roughness),
+                    other => {
+                        return Err(preset.invalid(&format!(
+                            "unknown preset \"{other}\", expected gold, copper, aluminium or silver"
+                        )));
+                    }
+                },
+                (None, None, Some(eta), Some(k)) => {
+                    Metal::conductor(eta.colour()?, k.colour()?, roughness)
+                }
+                _ => {
+                    return Err(node.invalid("expected either an albedo, a preset, or eta and k"));
+                }
+            };
+            Box::new(metal)
+        }
+        "dielectric" => {
+            node.expect_fields(&["type", "refraction_index"])?;
+            Box::new(Dielectric::new(
+                node.field("refraction_index")?.positive_number()?,
+            ))
+        }
+        "light" => {
+            node.expect_fields(&["type", "colour", "intensity", "two_sided", "surface"])?;
+            let mut light = Light::new(node.field("colour")?.colour()?);
+            if let Some(intensity) = node.get("intensity") {
+                light = light.with_intensity(intensity.positive_number()?);
+            }
+            if let Some(two_sided) = node.get("two_sided")
+                && !two_sided.boolean()?
+            {
+                light = light.one_sided();
+            }
+            if let Some(surface) = node.get("surface") {
+                light = light.with_surface(parse_material(&surface, base_dir)?);
+            }
+            Box::new(light)
+        }
+        other => {
+            return Err(node
+                .child("type")
+                .invalid(&format!("unknown material type \"{other}\"")));
+        }
+    };
+    Ok(Arc::new(material))
+}
+
+// A texture is either a plain colour or an object describing the texture.
+fn parse_texture(node: &Node, base_dir: &Path) -> Result<Arc<Box<dyn Texture>>, SceneError> {
+    if node.value.is_array() {
+        return Ok(Arc::new(Box::new(SolidColour::new(node.colour()?))));
+    }
+
+    let texture: Box<dyn Texture> = match node.field("type")?.string()? {
+        "solid" => {
+            node.expect_fields(&["type", "colour"])?;
+            Box::new(SolidColour::new(node.field("colour")?.colour()?))
+        }
+        "checker" => {
+            node.expect_fields(&["type", "scale", "even", "odd"])?;
+            Box::new(Checker::new(
+                node.field("scale")?.positive_number()?,
+                parse_texture(&node.field("even")?, base_dir)?,
+                parse_texture(&node.field("odd")?, base_dir)?,
+            ))
+        }
+        "image" => {
+            node.expect_fields(&["type", "file", "wrap"])?;
+            let wrap = match node.get("wrap") {
+                Some(wrap) => match wrap.string()? {
+                    "repeat" => WrapMode::Repeat,
+                    "mirror" => WrapMode::MirroredRepeat,
+                    "clamp" => WrapMode::Clamp,
+                    other => return Err(wrap.invalid(&format!("unknown wrap mode \"{other}\""))),
+                },
+                None => WrapMode::Repeat,
+            };
+            let file_node = node.field("file")?;
+            let file = base_dir.join(file_node.string()?);
+            Box::new(
+                ImageTexture::load(&file, wrap)
+                    .map_err(|err| file_node.invalid(&format!("{}: {err}", file.display())))?,
+            )
+        }
+        "noise" => {
+            node.expect_fields(&["type", "style", "scale", "colour"])?;
+            let style = match node.get("style") {
+                Some(style) => match style.string()? {
+                    "smooth" => NoiseStyle::Smooth,
+                    "turbulence" => NoiseStyle::Turbulence,
+                    "marble" => NoiseStyle::Marble,
+                    other => {
+                        return Err(style.invalid(&format!("unknown noise style \"{other}\"")));
+                    }
+                },
+                None => NoiseStyle::Marble,
+            };
+            let scale = match node.get("scale") {
+                Some(scale) => scale.positive_number()?,
+                None => 1.0,
+            };
+            let colour = match node.get("colour") {
+                Some(colour) => colour.colour()?,
+                None => Colour::new(1.0, 1.0, 1.0),
+            };
+            Box::new(NoiseTexture::new(scale, style, colour))
+        }
+        other => {
+            return Err(node
+                .child("type")
+                .invalid(&format!("unknown texture type \"{other}\"")));
+        }
+    };
+    Ok(Arc::new(texture))
+}
+
+fn parse_object(
+    node: &Node,
+    materials: &HashMap<String, Arc<Box<dyn Material + Sync + Send>>>,
+    base_dir: &Path,
+) -> Result<Vec<Arc<Box<dyn Hittable>>>, SceneError> {
+    let material = |node: &Node| -> Result<Arc<Box<dyn Material + Sync + Send>>, SceneError> {
+        let field = node.field("material")?;
+        let name = field.string()?;
+        materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| field.invalid(&format!("unknown material \"{name}\"")))
+    };
+    // Every type of object can be placed and moved.
+    let expect_fields = |fields: &[&str]| {
+        node.expect_fields(&[fields, &["transform", "instances", "motion", "moving"]].concat())
+    };
+
+    // The two ends of a cylinder or cone, which must be apart.
+    let axis_ends = |node: &Node| -> Result<(Point, Point), SceneError> {
+        let base = node.field("base")?.vector()?;
+        let top = node.field("top")?.vector()?;
+        match (top - base).magnitude2() > 0.0 {
+            true => Ok((base, top)),
+            false => Err(node.child("top").invalid("the top must not be at the base")),
+        }
+    };
+    // The opposite corners of a box, which must be apart along every axis.
+    let box_corners = |node: &Node| -> Result<(Point, Point), SceneError> {
+        let min = node.field("min")?.vector()?;
+        let max = node.field("max")?.vector()?;
+        match (0..3).find(|&axis| max[axis] <= min[axis]) {
+            Some(axis) => Err(node.child("max").invalid(&format!(
+                "max must be greater than min in {}",
+                ["x", "y", "z"][axis]
+            ))),
+            None => Ok((min, max)),
+        }
+    };
+    let caps = |node: &Node| match node.get("caps") {
+        Some(caps) => caps.boolean(),
+        None => Ok(true),
+    };
+
+    let placements = match (node.get("transform"), node.get("instances")) {
+        (Some(_), Some(instances)) => {
+            return Err(instances.invalid("an object cannot have both transform and instances"));
+        }
+        (Some(transform), None) => Some(vec![parse_transform(&transform)?]),
+        (None, Some(instances)) => Some(
+            instances
+                .elements()?
+                .iter()
+                .map(parse_transform)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        (None, None) => None,
+    };
+    let motion = match node.get("motion") {
+        Some(motion) => Some(motion.vector()?),
+        None => None,
+    };
+    let moving = match node.get("moving") {
+        Some(moving) => {
+            if let Some(other) = ["transform", "instances", "motion"]
+                .into_iter()
+                .find(|key| node.get(key).is_some())
+            {
+                return Err(
+                    moving.invalid(&format!("an object cannot have both moving and {other}"))
+                );
+            }
+            moving.expect_fields(&["start", "end"])?;
+            let start = parse_transform(&moving.field("start")?)?;
+            let end = parse_transform(&moving.field("end")?)?;
+            if start.decompose().is_reflection() != end.decompose().is_reflection() {
+                return Err(moving
+                    .child("end")
+                    .invalid("the start and end must both mirror the object or neither"));
+            }
+            Some((start, end))
+        }
+        None => None,
+    };
+
+    let objects: Vec<Arc<Box<dyn Hittable>>> = match node.field("type")?.string()? {
+        "sphere" => {
+            expect_fields(&["type", "centre", "radius", "material"])?;
+            let centre = node.field("centre")?.vector()?;
+            let radius = node.field("radius")?.positive_number()?;
+            if let (Some(motion), None) = (motion, &placements) {
+                let sphere = MovingSphere::new(centre, centre + motion, radius, material(node)?);
+                return Ok(vec![Arc::new(Box::new(sphere))]);
+            }
+            vec![Arc::new(Box::new(Sphere::new(
+                centre,
+                radius,
+                material(node)?,
+            )))]
+        }
+        "triangle" => {
+            expect_fields(&["type", "vertices", "material"])?;
+            let vertices = node.field("vertices")?.vectors()?;
+            let [a, b, c] = vertices[..] else {
+                return Err(node
+                    .child("vertices")
+                    .invalid("expected exactly three vertices"));
+            };
+            vec![Arc::new(Box::new(Triangle::new(a, b, c, material(node)?)))]
+        }
+        "quad" => {
+            expect_fields(&["type", "corner", "u", "v", "material"])?;
+            let corner = node.field("corner")?.vector()?;
+            let u = node.field("u")?.vector()?;
+            let v = node.field("v")?.vector()?;
+            if u.cross(v).magnitude2() == 0.0 {
+                return Err(node.invalid("the edges u and v must not be parallel"));
+            }
+            vec![Arc::new(Box::new(Quad::new(corner, u, v, material(node)?)))]
+        }
+        "plane" => {
+            expect_fields(&["type", "point", "normal", "material"])?;
+            let point = node.field("point")?.vector()?;
+            let normal = node.field("normal")?.non_zero_vector()?;
+            vec![Arc::new(Box::new(Plane::new(
+                point,
+                normal,
+                material(node)?,
+            )))]
+        }
+        "disk" => {
+            expect_fields(&["type", "centre", "normal", "radius", "material"])?;
+            let centre = node.field("centre")?.vector()?;
+            let normal = node.field("normal")?.non_zero_vector()?;
+            let radius = node.field("radius")?.positive_number()?;
+            vec![Arc::new(Box::new(Disk::new(
+                centre,
+                normal,
+                radius,
+                material(node)?,
+            )))]
+        }
+        "box" => {
+            expect_fields(&["type", "min", "max", "material"])?;
+            let (min, max) = box_corners(node)?;
+            vec![Arc::new(Box::new(BoxShape::new(min, max, material(node)?)))]
+        }
+        "cylinder" => {
+            expect_fields(&["type", "base", "top", "radius", "caps", "material"])?;
+            let (base, top) = axis_ends(node)?;
+            let radius = node.field("radius")?.positive_number()?;
+            let cylinder = Cylinder::new(base, top, radius, material(node)?).with_caps(caps(node)?);
+            vec![Arc::new(Box::new(cylinder))]
+        }
+        "cone" => {
+            expect_fields(&[
+                "type",
+                "base",
+                "top",
+                "base_radius",
+                "top_radius",
+                "caps",
+                "material",
+            ])?;
+            let (base, top) = axis_ends(node)?;
+            let base_radius = node.field("base_radius")?.non_negative_number()?;
+            let top_radius = match node.get("top_radius") {
+                Some(top_radius) => top_radius.non_negative_number()?,
+                None => 0.0,
+            };
+            if base_radius == 0.0 && top_radius == 0.0 {
+                return Err(node.invalid("a cone needs a radius above zero at one end"));
+            }
+            let cone = Cone::new(base, top, base_radius, top_radius, material(node)?)
+                .with_caps(caps(node)?);
+            vec![Arc::new(Box::new(cone))]
+        }
+        "capsule" => {
+            expect_fields(&["type", "base", "top", "radius", "material"])?;
+            let base = node.field("base")?.vector()?;
+            let top = node.field("top")?.vector()?;
+            let radius = node.field("radius")?.positive_number()?;
+            vec![Arc::new(Box::new(Capsule::new(
+                base,
+                top,
+                radius,
+                material(node)?,
+            )))]
+        }
+        "torus" => {
+            expect_fields(&[
+                "type",
+                "centre",
+                "axis",
+                "major_radius",
+                "minor_radius",
+                "material",
+            ])?;
+            let centre = node.field("centre")?.vector()?;
+            let axis = match node.get("axis") {
+                Some(axis) => axis.non_zero_vector()?,
+                None => Vector::new(0.0, 1.0, 0.0),
+            };
+            let major_radius = node.field("major_radius")?.positive_number()?;
+            let minor_radius = node.field("minor_radius")?.positive_number()?;
+            vec![Arc::new(Box::new(Torus::new(
+                centre,
+                axis,
+                major_radius,
+                minor_radius,
+                material(node)?,
+            )))]
+        }
+        "csg" => {
+            expect_fields(&["type", "operation", "a", "b"])?;
+            let operation_node = node.field("operation")?;
+            let operation: CsgOperation = operation_node
+                .string()?
+                .parse()
+                .map_err(|message: String| operation_node.invalid(&message))?;
+            vec![Arc::new(Box::new(Csg::new(
+                parse_solid(&node.field("a")?, materials, base_dir)?,
+                parse_solid(&node.field("b")?, materials, base_dir)?,
+                operation,
+            )))]
+        }
+        "medium" => {
+            expect_fields(&["type", "boundary", "density", "albedo"])?;
+            vec![Arc::new(Box::new(ConstantMedium::from_texture(
+                parse_solid(&node.field("boundary")?, materials, base_dir)?,
+                node.field("density")?.positive_number()?,
+                parse_texture(&node.field("albedo")?, base_dir)?,
+            )))]
+        }
+        "volume" => {
+            expect_fields(&[
+                "type",
+                "min",
+                "max",
+                "file",
+                "resolution",
+                "noise",
+                "density",
+                "albedo",
+                "anisotropy",
+            ])?;
+            let (min, max) = box_corners(node)?;
+            let bounds = Aabb::from_points(min, max);
+            let grid = match (node.get("file"), node.get("noise")) {
+                (Some(file_node), None) => {
+                    let file = base_dir.join(file_node.string()?);
+                    let grid = match node.get("resolution") {
+                        Some(resolution) => {
+                            DensityGrid::load_raw(&file, resolution.resolution()?, bounds)
+                        }
+                        None => DensityGrid::load(&file, bounds),
+                    };
+                    grid.map_err(|err| file_node.invalid(&format!("{}: {err}", file.display())))?
+                }
+                (None, Some(noise)) => {
+                    noise.expect_fields(&["resolution", "scale"])?;
+                    let scale = match noise.get("scale") {
+                        Some(scale) => scale.positive_number()?,
+                        None => 1.0,
+                    };
+                    DensityGrid::from_noise(noise.field("resolution")?.resolution()?, bounds, scale)
+                }
+                (Some(_), Some(noise)) => {
+                    return Err(noise.invalid("a volume cannot have both file and noise"));
+                }
+                (None, None) => return Err(node.invalid("expected a file or noise")),
+            };
+            let density = match node.get("density") {
+                Some(density) => density.positive_number()?,
+                None => 1.0,
+            };
+            let albedo = parse_texture(&node.field("albedo")?, base_dir)?;
+            let mut medium = GridMedium::from_texture(Arc::new(grid), density, albedo);
+            if let Some(anisotropy_node) = node.get("anisotropy") {
+                let anisotropy = anisotropy_node.number()?;
+                if anisotropy.abs() >= 1.0 {
+                    return Err(anisotropy_node.invalid("expected a number between -1 and 1"));
+                }
+                medium = medium.with_anisotropy(anisotropy);
+            }
+            vec![Arc::new(Box::new(medium))]
+        }
+        "sdf" => {
+            expect_fields(&["type", "shape", "material"])?;
+            let sdf = parse_sdf(&node.field("shape")?)?;
+            vec![Arc::new(Box::new(SdfShape::new(sdf, material(node)?)))]
+        }
+        "mesh" => {
+            expect_fields(&["type", "positions", "normals", "indices", "material"])?;
+            let positions = node.field("positions")?.vectors()?;
+            let indices = node.field("indices")?;
+            let indices = indices
+                .elements()?
+                .iter()
+                .map(|triangle| triangle.triangle_indices(positions.len()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mesh = match node.get("normals") {
+                Some(normals_node) => {
+                    let normals = normals_node.vectors()?;
+                    if normals.len() != positions.len() {
+                        return Err(normals_node.invalid(&format!(
+                            "expected one normal per position ({}), found {}",
+                            positions.len(),
+                            normals.len()
+                        )));
+                    }
+                    TriangleMesh::with_normals(positions, normals, indices, material(node)?)
+                }
+                None => TriangleMesh::new(positions, indices, material(node)?),
+            }
+            .map_err(|error| node.invalid(&error.to_string()))?;
+            vec![Arc::new(Box::new(mesh))]
+        }
+        "obj" => {
+            expect_fields(&["type", "file", "material"])?;
+            let file = base_dir.join(node.field("file")?.string()?);
+            let default_material = match node.get("material") {
+                Some(_) => material(node)?,
+                None => Arc::new(Box::new(Lambertian::new(Colour::new(0.8, 0.8, 0.8)))
+                    as Box<dyn Material + Sync + Send>),
+            };
+            let model = load_obj_with_default(&file, default_material).map_err(|error| {
+                SceneError::Obj {
+                    path: node.child("file").path,
+                    error,
+                }
+            })?;
+            // Each group is added to the scene separately so emissive groups can be
+            // found and sampled as lights.
+            model.into_hittable_list().objects().to_vec()
+        }
+        other => {
+            return Err(node
+                .child("type")
+                .invalid(&format!("unknown object type \"{other}\"")));
+        }
+    };
+
+    // Instances share the geometry, so a model is only loaded and built once however
+    // many copies of it are placed.
+    let objects = match placements {
+        Some(placements) => placements
+            .iter()
+            .flat_map(|transform| {
+                objects.iter().map(|object| {
+                    Arc::new(
+                        Box::new(Instance::new(object.clone(), *transform)) as Box<dyn Hittable>
+                    )
+                })
+            })
+            .collect(),
+        None => objects,
+    };
+
+    // A motion is a move from where the object was placed.
+    let moving = match motion {
+        Some(motion) => Some((Transform::IDENTITY, Transform::translate(motion))),
+        None => moving,
+    };
+    Ok(match moving {
+        Some((start, end)) => objects
+            .into_iter()
+            .map(|object| Arc::new(Box::new(Moving::new(object, start, end)) as Box<dyn Hittable>))
+            .collect(),
+        None => objects,
+    })
+}
+
+// A single object that encloses a volume, written out in full.
+fn parse_solid(
+    node: &Node,
+    materials: &HashMap<String, Arc<Box<dyn Material + Sync + Send>>>,
+    base_dir: &Path,
+) -> Result<Arc<Box<dyn Hittable>>, SceneError> {
+    let objects = parse_object(node, materials, base_dir)?;
+    // Whether an object is solid does not depend on the ray, so any will do.
+    let probe = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+    match &objects[..] {
+        [object] if object.spans(&probe).is_some() => Ok(object.clone()),
+        [_] => Err(node.invalid("expected a closed solid")),
+        _ => Err(node.invalid("expected a single object")),
+    }
+}
+
+fn parse_sdf(node: &Node) -> Result<Arc<Box<dyn Sdf>>, SceneError> {
+    let sdf: Box<dyn Sdf> = match node.field("type")?.string()? {
+        "sphere" => {
+            node.expect_fields(&["type", "centre", "radius"])?;
+            Box::new(SdfSphere::new(
+                node.field("centre")?.vector()?,
+                node.field("radius")?.positive_number()?,
+            ))
+        }
+        "box" => {
+            node.expect_fields(&["type", "centre", "size", "corner_radius"])?;
+            let sdf_box = SdfBox::new(
+                node.field("centre")?.vector()?,
+                node.field("size")?.vector()?,
+            );
+            match node.get("corner_radius") {
+                Some(radius) => Box::new(sdf_box.with_corner_radius(radius.non_negative_number()?)),
+                None => Box::new(sdf_box),
+            }
+        }
+        "torus" => {
+            node.expect_fields(&["type", "centre", "major_radius", "minor_radius"])?;
+            Box::new(SdfTorus::new(
+                node.field("centre")?.vector()?,
+                node.field("major_radius")?.positive_number()?,
+                node.field("minor_radius")?.positive_number()?,
+            ))
+        }
+        combinator @ ("union" | "subtraction" | "intersection") => {
+            node.expect_fields(&["type", "a", "b", "smoothness"])?;
+            let a = parse_sdf(&node.field("a")?)?;
+            let b = parse_sdf(&node.field("b")?)?;
+            let smoothness = match node.get("smoothness") {
+                Some(smoothness) => smoothness.non_negative_number()?,
+                None => 0.0,
+            };
+            match combinator {
+                "union" => Box::new(SmoothUnion::new(a, b, smoothness)),
+                "subtraction" => Box::new(SmoothSubtraction::new(a, b, smoothness)),
+                _ => Box::new(SmoothIntersection::new(a, b, smoothness)),
+            }
+        }
+        other => {
+            return Err(node
+                .child("type")
+                .invalid(&format!("unknown distance function type \"{other}\"")));
+        }
+    };
+    Ok(Arc::new(sdf))
+}
+
+// A transform is a list of steps applied in order, each an object with one of the
+// keys translate, scale, rotate or look_at.
+fn parse_transform(node: &Node) -> Result<Transform, SceneError> {
+    let mut transform = Transform::IDENTITY;
+    for step in node.elements()? {
+        let entries = step.entries()?;
+        let (name, value) = match &entries[..] {
+            [(name, value)] => (*name, value),
+            _ => return Err(step.invalid("expected an object with a single key")),
+        };
+        let next = match name {
+            "translate" => Transform::translate(value.vector()?),
+            "scale" => {
+                let factors = match value.value.as_f64() {
+                    Some(_) => {
+                        let factor = value.number()?;
+                        Vector::new(factor, factor, factor)
+                    }
+                    None => value.vector()?,
+                };
+                if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+                    return Err(value.invalid("scale factors must be non-zero"));
+                }
+                Transform::scale(factors)
+            }
+            "rotate" => {
+                value.expect_fields(&["axis", "degrees"])?;
+                Transform::rotate(
+                    value.field("axis")?.non_zero_vector()?,
+                    value.field("degrees")?.number()?,
+                )
+            }
+            "look_at" => {
+                value.expect_fields(&["from", "to", "up"])?;
+                let from = value.field("from")?.vector()?;
+                let to = value.field("to")?.vector()?;
+                if from == to {
+                    return Err(value.child("to").invalid("to must not be the same as from"));
+                }
+                let up_node = value.get("up");
+                let up = match &up_node {
+                    Some(up) => up.non_zero_vector()?,
+                    None => Vector::new(0.0, 1.0, 0.0),
+                };
+                // Up only settles which way round the object is turned about the line
+                // from from to to, so it must not lie along it.
+                if up.normalize().cross((to - from).normalize()).magnitude() <= 1e-9 {
+                    return Err(match up_node {
+                        Some(up) => {
+                            up.invalid("up must not be parallel to the direction from from to to")
+                        }
+                        None => value.invalid("up must be given when looking straight up or down"),
+                    });
+                }
+                Transform::look_at(from, to, up)
+            }
+            other => {
+                return Err(step.invalid(&format!("unknown transform step \"{other}\"")));
+            }
+        };
+        transform = transform.then(next);
+    }
+    Ok(transform)
+}
+
+// A JSON value along with its path from the root of the document, used to point
+// validation errors at the offending value.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn root(value: &'a Value) -> Self {
+        Node {
+            value,
+            path: String::new(),
+        }
+    }
+
+    fn invalid(&self, message: &str) -> SceneError {
+        SceneError::Invalid {
+            path: match self.path.is_empty() {
+                true => String::from("(root)"),
+                false => self.path.clone(),
+            },
+            message: message.to_string(),
+        }
+    }
+
+    fn child_path(&self, key: &str) -> String {
+        match self.path.is_empty() {
+            true => key.to_string(),
+            false => format!("{}.{}", self.path, key),
+        }
+    }
+
+    // A node for a field that may not exist, for errors about missing values.
+    fn child(&self, key: &str) -> Node<'a> {
+        Node {
+            value: self.value.get(key).unwrap_or(&Value::Null),
+            path: self.child_path(key),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Node<'a>> {
+        self.value.get(key).map(|value| Node {
+            value,
+            path: self.child_path(key),
+        })
+    }
+
+    fn field(&self, key: &str) -> Result<Node<'a>, SceneError> {
+        self.get(key)
+            .ok_or_else(|| self.child(key).invalid("missing required field"))
+    }
+
+    fn object(&self) -> Result<&'a Map<String, Value>, SceneError> {
+        self.value
+            .as_object()
+            .ok_or_else(|| self.invalid("expected an object"))
+    }
+
+    // Rejects unknown fields, which are most likely typos.
+    fn expect_fields(&self, allowed: &[&str]) -> Result<(), SceneError> {
+        match self
+            .object()?
+            .keys()
+            .find(|key| !allowed.contains(&key.as_str()))
+        {
+            Some(key) => Err(self.child(key).invalid("unknown field")),
+            None => Ok(()),
+        }
+    }
+
+    fn entries(&self) -> Result<Vec<(&'a str, Node<'a>)>, SceneError> {
+        Ok(self
+            .object()?
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.as_str(),
+                    Node {
+                        value,
+                        path: self.child_path(key),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    fn elements(&self) -> Result<Vec<Node<'a>>, SceneError> {
+        let array = self
+            .value
+            .as_array()
+            .ok_or_else(|| self.invalid("expected an array"))?;
+        Ok(array
+            .iter()
+            .enumerate()
+            .map(|(index, value)| Node {
+                value,
+                path: format!("{}[{}]", self.path, index),
+            })
+            .collect())
+    }
+
+    fn string(&self) -> Result<&'a str, SceneError> {
+        self.value
+            .as_str()
+            .ok_or_else(|| self.invalid("expected a string"))
+    }
+
+    fn boolean(&self) -> Result<bool, SceneError> {
+        self.value
+            .as_bool()
+            .ok_or_else(|| self.invalid("expected true or false"))
+    }
+
+    fn number(&self) -> Result<f64, SceneError> {
+        self.value
+            .as_f64()
+            .ok_or_else(|| self.invalid("expected a number"))
+    }
+
+    fn positive_number(&self) -> Result<f64, SceneError> {
+        match self.number()? {
+            number if number > 0.0 => Ok(number),
+            number => Err(self.invalid(&format!("expected a positive number, found {number}"))),
+        }
+    }
+
+    fn non_negative_number(&self) -> Result<f64, SceneError> {
+        match self.number()? {
+            number if number >= 0.0 => Ok(number),
+            number => Err(self.invalid(&format!("expected a non-negative number, found {number}"))),
+        }
+    }
+
+    fn positive_integer(&self) -> Result<i32, SceneError> {
+        match self.value.as_i64() {
+            Some(integer) if integer > 0 && integer <= i32::MAX as i64 => Ok(integer as i32),
+            _ => Err(self.invalid("expected a positive integer")),
+        }
+    }
+
+    fn non_negative_integer(&self) -> Result<i32, SceneError> {
+        match self.value.as_i64() {
+            Some(integer) if integer >= 0 && integer <= i32::MAX as i64 => Ok(integer as i32),
+            _ => Err(self.invalid("expected a non-negative integer")),
+        }
+    }
+
+    fn vector(&self) -> Result<Vector, SceneError> {
+        match self.value.as_array().map(|array| &array[..]) {
+            Some([x, y, z]) => match (x.as_f64(), y.as_f64(), z.as_f64()) {
+                (Some(x), Some(y), Some(z)) => Ok(Vector::new(x, y, z)),
+                _ => Err(self.invalid("expected an array of three numbers")),
+            },
+            _ => Err(self.invalid("expected an array of three numbers")),
+        }
+    }
+
+    fn non_zero_vector(&self) -> Result<Vector, SceneError> {
+        let vector = self.vector()?;
+        match vector.magnitude2() > 0.0 {
+            true => Ok(vector),
+            false => Err(self.invalid("expected a non-zero vector")),
+        }
+    }
+
+    fn colour(&self) -> Result<Colour, SceneError> {
+        let colour = self.vector()?;
+        match colour.x >= 0.0 && colour.y >= 0.0 && colour.z >= 0.0 {
+            true => Ok(colour),
+            false => Err(self.invalid("colour components cannot be negative")),
+        }
+    }
+
+    // The number of voxels along each axis of a grid.
+    // The size of a voxel grid, which must be within the limits of DensityGrid.
+    fn resolution(&self) -> Result<[usize; 3], SceneError> {
+        let resolution = match self.value.as_array().map(|array| &array[..]) {
+            Some([x, y, z]) => match (x.as_u64(), y.as_u64(), z.as_u64()) {
+                (Some(x), Some(y), Some(z)) if x > 0 && y > 0 && z > 0 => {
+                    [x, y, z].map(|size| usize::try_from(size).unwrap_or(usize::MAX))
+                }
+                _ => return Err(self.invalid("expected an array of three positive integers")),
+            },
+            _ => return Err(self.invalid("expected an array of three positive integers")),
+        };
+        match voxel_count(resolution) {
+            Some(_) => Ok(resolution),
+            None => Err(self.invalid(&format!(
+                "expected at most {MAX_RESOLUTION} voxels along each axis and {MAX_VOXELS} in total"
+            ))),
+        }
+    }
+
+    fn vectors(&self) -> Result<Vec<Point>, SceneError> {
+        self.elements()?.iter().map(Node::vector).collect()
+    }
+
+    fn triangle_indices(&self, vertex_count: usize) -> Result<[usize; 3], SceneError> {
+        let elements = self.elements()?;
+        if elements.len() != 3 {
+            return Err(self.invalid("expected an array of three indices"));
+        }
+        let mut indices = [0; 3];
+        for (index, element) in indices.iter_mut().zip(&elements) {
+            *index = match element.value.as_u64() {
+                Some(value) if (value as usize) < vertex_count => value as usize,
+                _ => {
+                    return Err(element.invalid(&format!(
+                        "expected an index below the {vertex_count} positions"
+                    )));
+                }
+            };
+        }
+        Ok(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // The message a scene is rejected with.
+    fn error(scene: Value) -> String {
+        match parse_scene(&scene, Path::new("")) {
+            Ok(_) => panic!("expected the scene to be rejected"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    // A scene with a single grey sphere placed by transform.
+    fn transformed_sphere(transform: Value) -> Value {
+        json!({
+            "materials": { "grey": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] } },
+            "objects": [
+                { "type": "sphere", "centre": [0.0, 0.0, 0.0], "radius": 1.0, "material": "grey",
+                  "transform": transform }
+            ]
+        })
+    }
+
+    #[test]
+    fn unknown_fields_are_reported_where_they_are() {
+        assert_eq!(error(json!({ "cameras": {} })), "cameras: unknown field");
+        assert_eq!(
+            error(json!({ "camera": { "image_width": 64, "fov": 40.0 } })),
+            "camera.fov: unknown field"
+        );
+        assert_eq!(
+            error(json!({
+                "materials": { "red": { "type": "lambertian", "albedo": [0.8, 0.1, 0.1], "fuzz": 0.2 } }
+            })),
+            "materials.red.fuzz: unknown field"
+        );
+        assert_eq!(
+            error(json!({
+                "materials": { "red": { "type": "lambertian", "albedo": [0.8, 0.1, 0.1] } },
+                "objects": [
+                    { "type": "sphere", "centre": [0, 0, 0], "radius": 1.0, "material": "red" },
+                    { "type": "sphere", "centre": [0, 0, 0], "radius": 1.0, "material": "red",
+                      "colour": [1.0, 0.0, 0.0] }
+                ]
+            })),
+            "objects[1].colour: unknown field"
+        );
+        assert_eq!(
+            error(json!({
+                "objects": [
+                    { "type": "sphere", "centre": [0, 0, 0], "radius": 1.0, "material": "red",
+                      "transform": [{ "translate": [1, 0, 0] }, { "spin": 20.0 }] }
+                ]
+            })),
+            "objects[0].transform[1]: unknown transform step \"spin\""
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_reported_where_they_are() {
+        assert_eq!(
+            error(json!({ "materials": { "shiny": { "type": "chrome" } } })),
+            "materials.shiny.type: unknown material type \"chrome\""
+        );
+        assert_eq!(
+            error(json!({
+                "objects": [{ "type": "sphere", "centre": [0, 0, 0], "radius": 1.0, "material": "gold" }]
+            })),
+            "objects[0].material: unknown material \"gold\""
+        );
+        assert_eq!(
+            error(json!({ "objects": [{ "type": "cube" }] })),
+            "objects[0].type: unknown object type \"cube\""
+        );
+        assert_eq!(
+            error(json!({ "objects": [{ "centre": [0, 0, 0] }] })),
+            "objects[0].type: missing required field"
+        );
+    }
+
+    // A light filling the middle of the view against a plain background, rendered
+    // through the framebuffer: pixels see one or the other exactly.
+    #[test]
+    fn small_scenes_render() {
+        let scene = json!({
+            "camera": {
+                "aspect_ratio": 1.0, "image_width": 9, "samples_per_pixel": 4, "vfov": 40.0,
+                "lookfrom": [0.0, 0.0, 5.0], "lookat": [0.0, 0.0, 0.0], "defocus_angle": 0.0
+            },
+            "environment": [0.25, 0.5, 0.75],
+            "materials": { "lamp": { "type": "light", "colour": [4.0, 2.0, 1.0] } },
+            "objects": [
+                { "type": "sphere", "centre": [0.0, 0.0, 0.0], "radius": 0.5, "material": "lamp" }
+            ]
+        });
+        let scene = parse_scene(&scene, Path::new("")).unwrap();
+        let mut camera = scene.camera.build();
+        camera.set_seed(3);
+        let framebuffer = camera.render(&scene.world, scene.camera.integrator().as_ref(), 2);
+
+        assert_eq!((framebuffer.width(), framebuffer.height()), (9, 9));
+        let close = |a: Colour, b: Colour| a.approx_eq(&b, 1e-9);
+        assert!(close(framebuffer.get(4, 4), Colour::new(4.0, 2.0, 1.0)));
+        for (x, y) in [(0, 0), (8, 0), (0, 8), (8, 8), (4, 0)] {
+            assert!(close(framebuffer.get(x, y), Colour::new(0.25, 0.5, 0.75)));
+        }
+    }
+
+    #[test]
+    fn environments_are_validated() {
+        let scene = json!({
+            "environment": { "type": "sky", "sun_direction": [0.0, 0.0, 0.0] }
+        });
+        assert_eq!(
+            error(scene),
+            "environment.sun_direction: expected a non-zero vector"
+        );
+
+        let scene = json!({
+            "environment": { "type": "sky", "sun_direction": [0.0, 1.0, 0.0], "intensity": -1.0 }
+        });
+        assert_eq!(
+            error(scene),
+            "environment.intensity: expected a positive number, found -1"
+        );
+    }
+
+    #[test]
+    fn boxes_must_not_be_flat() {
+        let scene = json!({
+            "materials": { "grey": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] } },
+            "objects": [
+                { "type": "box", "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 1.0], "material": "grey" },
+                { "type": "box", "min": [0.0, 0.0, 0.0], "max": [1.0, 0.0, 1.0], "material": "grey" }
+            ]
+        });
+        assert_eq!(
+            error(scene),
+            "objects[1].max: max must be greater than min in y"
+        );
+
+        let inside_out = json!({
+            "materials": { "grey": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] } },
+            "objects": [
+                { "type": "box", "min": [0.0, 0.0, 0.0], "max": [-1.0, 1.0, 1.0], "material": "grey" }
+            ]
+        });
+        assert_eq!(
+            error(inside_out),
+            "objects[0].max: max must be greater than min in x"
+        );
+    }
+
+    #[test]
+    fn volumes_take_a_textured_albedo() {
+        let volume = |albedo: Value, max: Value| {
+            json!({
+                "objects": [
+                    { "type": "volume", "min": [0.0, 0.0, 0.0], "max": max,
+                      "noise": { "resolution": [4, 4, 4] }, "albedo": albedo }
+                ]
+            })
+        };
+        let marble = json!({ "type": "noise", "style": "marble", "scale": 2.0 });
+        assert!(
+            parse_scene(
+                &volume(marble.clone(), json!([1.0, 1.0, 1.0])),
+                Path::new("")
+            )
+            .is_ok()
+        );
+        assert!(
+            parse_scene(
+                &volume(json!([0.9, 0.9, 0.9]), json!([1.0, 1.0, 1.0])),
+                Path::new("")
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            error(volume(marble, json!([1.0, 1.0, -1.0]))),
+            "objects[0].max: max must be greater than min in z"
+        );
+    }
+
+    #[test]
+    fn huge_grids_are_rejected() {
+        let scene = json!({
+            "objects": [
+                { "type": "volume", "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 1.0],
+                  "noise": { "resolution": [100000, 100000, 100000] }, "albedo": [1.0, 1.0, 1.0] }
+            ]
+        });
+        assert_eq!(
+            error(scene),
+            format!(
+                "objects[0].noise.resolution: expected at most {MAX_RESOLUTION} voxels along each \
+                 axis and {MAX_VOXELS} in total"
+            )
+        );
+    }
+
+    #[test]
+    fn rotations_need_an_axis() {
+        let scene = transformed_sphere(json!([
+            { "rotate": { "axis": [0.0, 0.0, 0.0], "degrees": 30.0 } }
+        ]));
+        assert_eq!(
+            error(scene),
+            "objects[0].transform[0].rotate.axis: expected a non-zero vector"
+        );
+    }
+
+    #[test]
+    fn look_at_needs_a_direction() {
+        let scene = transformed_sphere(json!([
+            { "look_at": { "from": [1.0, 2.0, 3.0], "to": [1.0, 2.0, 3.0] } }
+        ]));
+        assert_eq!(
+            error(scene),
+            "objects[0].transform[0].look_at.to: to must not be the same as from"
+        );
+
+        let scene = transformed_sphere(json!([
+            { "look_at": { "from": [0.0, 0.0, 0.0], "to": [1.0, 1.0, 0.0], "up": [-2.0, -2.0, 0.0] } }
+        ]));
+        assert_eq!(
+            error(scene),
+            "objects[0].transform[0].look_at.up: up must not be parallel to the direction from from to to"
+        );
+
+        let scene = transformed_sphere(json!([
+            { "look_at": { "from": [0.0, 0.0, 0.0], "to": [0.0, -3.0, 0.0] } }
+        ]));
+        assert_eq!(
+            error(scene),
+            "objects[0].transform[0].look_at: up must be given when looking straight up or down"
+        );
+
+        let scene = transformed_sphere(json!([
+            { "look_at": { "from": [0.0, 0.0, 0.0], "to": [0.0, -3.0, 0.0], "up": [0.0, 0.0, 1.0] } }
+        ]));
+        assert!(parse_scene(&scene, Path::new("")).is_ok());
+    }
+}
@@ -0,0 +1,383 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span, oriented_hit_record, pair_hits},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    sphere::sphere_uv,
+    vector::{Point, Vector},
+};
+
+// Marching gives up after this many steps, which only happens for rays that skim
+// along a surface without reaching it.
+const MAX_STEPS: usize = 512;
+
+// How close to the surface a point must come to count as a hit.
+const HIT_DISTANCE: f64 = 1e-6;
+
+// Step used to estimate normals from the distance function.
+const NORMAL_STEP: f64 = 1e-6;
+
+// A signed distance function: the distance from a point to the nearest surface,
+// negative inside the shape. It may underestimate the distance, as the smooth
+// combinators do, but never overestimate it.
+pub trait Sdf: Sync + Send {
+    fn distance(&self, point: Point) -> f64;
+
+    // A box the whole shape fits inside.
+    fn bounding_box(&self) -> Aabb;
+}
+
+// A shape given by a distance function, found by sphere tracing: stepping along the
+// ray by the distance to the nearest surface, which can never overshoot it.
+// Normals are estimated by central differences. Textures are wrapped around the
+// centre of the bounding box as on a sphere.
+pub struct SdfShape {
+    sdf: Arc<Box<dyn Sdf>>,
+    material: Arc<Box<dyn Material + Sync + Send>>,
+    bbox: Aabb,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Arc<Box<dyn Sdf>>, material: Arc<Box<dyn Material + Sync + Send>>) -> Self {
+        // Marching stops where the ray leaves the box, so it is widened a little to
+        // keep surfaces that touch it, such as the poles of a sphere, within reach.
+        let bbox = sdf.bounding_box();
+        let margin = 4.0 * HIT_DISTANCE;
+        let bbox = Aabb::new(
+            bbox.x.expand(margin),
+            bbox.y.expand(margin),
+            bbox.z.expand(margin),
+        );
+        Self {
+            sdf,
+            material,
+            bbox,
+        }
+    }
+
+    fn normal(&self, point: Point) -> Vector {
+        let offset = |x: f64, y: f64, z: f64| {
+            let step = Vector::new(x, y, z) * NORMAL_STEP;
+            self.sdf.distance(point + step) - self.sdf.distance(point - step)
+        };
+        Vector::new(
+            offset(1.0, 0.0, 0.0),
+            offset(0.0, 1.0, 0.0),
+            offset(0.0, 0.0, 1.0),
+        )
+        .normalize()
+    }
+}
+
+impl SdfShape {
+    // Steps along the ray from t, on the given side of the surface, until it is
+    // reached or t passes t_max.
+    fn march(&self, ray: &Ray, mut t: f64, t_max: f64, side: f64) -> Option<f64> {
+        let speed = ray.direction.magnitude();
+        for _ in 0..MAX_STEPS {
+            if t >= t_max {
+                return None;
+            }
+            let distance = side * self.sdf.distance(ray.at(t));
+            if distance < HIT_DISTANCE {
+                return Some(t);
+            }
+            t += distance / speed;
+        }
+        None
+    }
+
+    fn hit_record(&self, ray: &Ray, t: f64) -> HitRecord {
+        let point = ray.at(t);
+        let uv = sphere_uv((point - self.bbox.centroid()).normalize());
+        oriented_hit_record(ray, t, self.normal(point), uv, &self.material)
+    }
+}
+
+impl Hittable for SdfShape {
+    // Rays starting inside the shape march out to its surface the same way, so
+    // refractive materials work.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let ray_t = self.bbox.clip(ray, ray_t)?;
+        let side = self.sdf.distance(ray.at(ray_t.min)).signum();
+        let t = self.march(ray, ray_t.min, ray_t.max, side)?;
+        Some(self.hit_record(ray, t))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Marching again from a hit would find the same surface, so after each one the
+    // ray is first stepped through to the other side.
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        let Some(ray_t) = self.bbox.clip(ray, Interval::UNIVERSE) else {
+            return Some(Vec::new());
+        };
+        let speed = ray.direction.magnitude();
+        let mut side = self.sdf.distance(ray.at(ray_t.min)).signum();
+        let mut t = ray_t.min;
+        let mut hits = Vec::new();
+        while let Some(hit) = self.march(ray, t, ray_t.max, side) {
+            hits.push(self.hit_record(ray, hit));
+            side = -side;
+            t = hit;
+            for _ in 0..MAX_STEPS {
+                let distance = side * self.sdf.distance(ray.at(t));
+                if distance >= HIT_DISTANCE {
+                    break;
+                }
+                t += (2.0 * HIT_DISTANCE - distance) / speed;
+            }
+        }
+        Some(pair_hits(hits.into_iter()))
+    }
+}
+
+pub struct SdfSphere {
+    centre: Point,
+    radius: f64,
+}
+
+impl SdfSphere {
+    pub fn new(centre: Point, radius: f64) -> Self {
+        Self { centre, radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, point: Point) -> f64 {
+        (point - self.centre).magnitude() - self.radius
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = Vector::new(self.radius, self.radius, self.radius);
+        Aabb::from_points(self.centre - extent, self.centre + extent)
+    }
+}
+
+// An axis-aligned box of the given size, with its edges and corners rounded off
+// to corner_radius if one is set.
+pub struct SdfBox {
+    centre: Point,
+    half_size: Vector,
+    corner_radius: f64,
+}
+
+impl SdfBox {
+    pub fn new(centre: Point, size: Vector) -> Self {
+        Self {
+            centre,
+            half_size: size.abs() / 2.0,
+            corner_radius: 0.0,
+        }
+    }
+
+    // Clamped to half the smallest side.
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.corner_radius = radius.clamp(0.0, self.half_size.min_component());
+        self
+    }
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, point: Point) -> f64 {
+        let corner = Vector::new(1.0, 1.0, 1.0) * self.corner_radius;
+        let q = (point - self.centre).abs() - (self.half_size - corner);
+        let zero = Vector::new(0.0, 0.0, 0.0);
+        q.max(zero).magnitude() + q.max_component().min(0.0) - self.corner_radius
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(self.centre - self.half_size, self.centre + self.half_size)
+    }
+}
+
+// A ring of major_radius around the vertical axis through centre, with a tube of
+// minor_radius.
+pub struct SdfTorus {
+    centre: Point,
+    major_radius: f64,
+    minor_radius: f64,
+}
+
+impl SdfTorus {
+    pub fn new(centre: Point, major_radius: f64, minor_radius: f64) -> Self {
+        Self {
+            centre,
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, point: Point) -> f64 {
+        let p = point - self.centre;
+        let ring = (p.x * p.x + p.z * p.z).sqrt() - self.major_radius;
+        (ring * ring + p.y * p.y).sqrt() - self.minor_radius
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vector::new(outer, self.minor_radius, outer);
+        Aabb::from_points(self.centre - extent, self.centre + extent)
+    }
+}
+
+// Both shapes, blended together where they come within smoothness of each other.
+pub struct SmoothUnion {
+    a: Arc<Box<dyn Sdf>>,
+    b: Arc<Box<dyn Sdf>>,
+    smoothness: f64,
+}
+
+impl SmoothUnion {
+    pub fn new(a: Arc<Box<dyn Sdf>>, b: Arc<Box<dyn Sdf>>, smoothness: f64) -> Self {
+        Self { a, b, smoothness }
+    }
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, point: Point) -> f64 {
+        smooth_min(
+            self.a.distance(point),
+            self.b.distance(point),
+            self.smoothness,
+        )
+    }
+
+    // The blend can bulge out by a quarter of the smoothness.
+    fn bounding_box(&self) -> Aabb {
+        let bbox = Aabb::enclosing(&self.a.bounding_box(), &self.b.bounding_box());
+        let margin = self.smoothness / 2.0;
+        Aabb::new(
+            bbox.x.expand(margin),
+            bbox.y.expand(margin),
+            bbox.z.expand(margin),
+        )
+    }
+}
+
+// Shape a with shape b carved out of it, with the edges rounded by smoothness.
+pub struct SmoothSubtraction {
+    a: Arc<Box<dyn Sdf>>,
+    b: Arc<Box<dyn Sdf>>,
+    smoothness: f64,
+}
+
+impl SmoothSubtraction {
+    pub fn new(a: Arc<Box<dyn Sdf>>, b: Arc<Box<dyn Sdf>>, smoothness: f64) -> Self {
+        Self { a, b, smoothness }
+    }
+}
+
+impl Sdf for SmoothSubtraction {
+    fn distance(&self, point: Point) -> f64 {
+        -smooth_min(
+            -self.a.distance(point),
+            self.b.distance(point),
+            self.smoothness,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.a.bounding_box()
+    }
+}
+
+// Only where the shapes overlap, with the edges rounded by smoothness.
+pub struct SmoothIntersection {
+    a: Arc<Box<dyn Sdf>>,
+    b: Arc<Box<dyn Sdf>>,
+    smoothness: f64,
+}
+
+impl SmoothIntersection {
+    pub fn new(a: Arc<Box<dyn Sdf>>, b: Arc<Box<dyn Sdf>>, smoothness: f64) -> Self {
+        Self { a, b, smoothness }
+    }
+}
+
+impl Sdf for SmoothIntersection {
+    fn distance(&self, point: Point) -> f64 {
+        -smooth_min(
+            -self.a.distance(point),
+            -self.b.distance(point),
+            self.smoothness,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.a.bounding_box()
+    }
+}
+
+// The polynomial smooth minimum, which is the plain minimum when smoothness is zero.
+fn smooth_min(a: f64, b: f64, smoothness: f64) -> f64 {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / smoothness).clamp(0.0, 1.0);
+    b * (1.0 - h) + a * h - smoothness * h * (1.0 - h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian, sphere::Sphere};
+
+    #[test]
+    fn marched_spheres_match_analytic_ones() {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        let centre = Point::new(1.0, 2.0, -3.0);
+        let marched = SdfShape::new(
+            Arc::new(Box::new(SdfSphere::new(centre, 1.5))),
+            material.clone(),
+        );
+        let analytic = Sphere::new(centre, 1.5, material);
+
+        let rays = [
+            Ray::new(Point::new(1.0, 2.0, 5.0), Vector::new(0.0, 0.0, -1.0)),
+            Ray::new(Point::new(-4.0, 0.0, -2.0), Vector::new(2.5, 1.0, -0.5)),
+            Ray::new(Point::new(1.2, 6.0, -3.4), Vector::new(0.0, -3.0, 0.0)),
+            // From inside, as for a refracted ray.
+            Ray::new(Point::new(1.5, 2.0, -3.0), Vector::new(0.3, 0.4, 1.0)),
+        ];
+        for ray in rays {
+            let ray_t = Interval::new(0.001, f64::INFINITY);
+            let expected = analytic
+                .hit(&ray, ray_t)
+                .expect("ray should hit the sphere");
+            let rec = marched
+                .hit(&ray, ray_t)
+                .expect("ray should hit the SDF sphere");
+            assert!(
+                (rec.t - expected.t).abs() < 1e-5,
+                "{} != {}",
+                rec.t,
+                expected.t
+            );
+            assert!(rec.normal.approx_eq(&expected.normal, 1e-5));
+            assert_eq!(rec.front_face, expected.front_face);
+
+            let spans = marched.spans(&ray).unwrap();
+            assert_eq!(spans.len(), 1);
+            let exit = analytic
+                .hit(&ray, Interval::new(spans[0].enter.t + 0.001, f64::INFINITY))
+                .expect("ray should leave the sphere");
+            assert!((spans[0].exit.t - exit.t).abs() < 1e-5);
+        }
+
+        let miss = Ray::new(Point::new(3.0, 2.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(
+            marched
+                .hit(&miss, Interval::new(0.001, f64::INFINITY))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::f64::consts::PI;
+
+use crate::{
+    Colour,
+    environment::Environment,
+    material::random_unit_vector,
+    rng::random,
+    vector::{Onb, Vector, dot},
+};
+
+// Chance of sampling the sun disk rather than the whole sky.
+const SUN_SAMPLE_PROBABILITY: f64 = 0.5;
+
+// Luminance of the sun above the atmosphere, in the sky model's units of thousands
+// of candela per square metre.
+const SUN_LUMINANCE: f64 = 1.96e6;
+
+// Preetham et al.'s analytic daylight model: the sky's brightness and colour from
+// the sun's direction and the turbidity of the air, which runs from about 2 for a
+// clear day to 10 for haze. Below the horizon is a flat ground of the given albedo,
+// lit by the sky and sun. The sun is a disk, the angular diameter of which defaults
+// to the real sun's 0.53 degrees, dimmed and reddened by the air it passes through.
+//
+// Brightness is scaled so that a white horizontal surface under the open sky
+// reflects a radiance of intensity, which keeps the exposure steady as the sun moves.
+pub struct PreethamSky {
+    sun_direction: Vector,
+    // The Perez coefficients A to E, and the zenith value, of Y, x and y.
+    perez: [[f64; 5]; 3],
+    zenith: [f64; 3],
+    sun_colour: Colour,
+    cos_sun_radius: f64,
+    ground: Colour,
+    intensity: f64,
+    // Unscaled light from the sky and sun falling on a horizontal surface.
+    irradiance: Colour,
+}
+
+impl PreethamSky {
+    pub fn new(sun_direction: Vector, turbidity: f64, ground_albedo: Colour) -> Self {
+        let sun_direction = sun_direction.normalize();
+        // The model is only fitted for the sun above the horizon.
+        let theta_sun = sun_direction.y.clamp(0.0, 1.0).acos();
+        let t = turbidity;
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_chromaticity = |coefficients: [[f64; 4]; 3]| {
+            let powers = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+            let row = |row: [f64; 4]| (0..4).map(|i| row[i] * powers[i]).sum::<f64>();
+            t * t * row(coefficients[0]) + t * row(coefficients[1]) + row(coefficients[2])
+        };
+        let zenith = [
+            zenith_luminance.max(0.0),
+            zenith_chromaticity([
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ]),
+            zenith_chromaticity([
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ]),
+        ];
+
+        let mut sky = PreethamSky {
+            sun_direction,
+            perez,
+            zenith,
+            sun_colour: sun_colour(theta_sun, turbidity),
+            cos_sun_radius: 1.0,
+            ground: ground_albedo,
+            intensity: 1.0,
+            irradiance: Colour::new(0.0, 0.0, 0.0),
+        };
+        sky.set_sun_angle(0.53);
+        sky
+    }
+
+    // The angular diameter of the sun disk.
+    pub fn with_sun_angle(mut self, degrees: f64) -> Self {
+        self.set_sun_angle(degrees);
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    fn set_sun_angle(&mut self, degrees: f64) {
+        self.cos_sun_radius = (0.5 * degrees).to_radians().cos();
+        self.irradiance = self.sky_irradiance() + self.sun_irradiance();
+    }
+
+    fn scale(&self) -> f64 {
+        let irradiance = luminance(self.irradiance);
+        match irradiance > 0.0 {
+            true => self.intensity * PI / irradiance,
+            false => self.intensity,
+        }
+    }
+
+    // Unscaled radiance of the sky, without the sun, for a direction above the horizon.
+    fn sky_radiance(&self, direction: Vector) -> Colour {
+        let cos_theta = direction.y.max(0.001);
+        let cos_gamma = dot(direction, self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+        let theta_sun = self.sun_direction.y.clamp(0.0, 1.0).acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let [a, b, c, d, e] = self.perez[i];
+            let perez = |cos_theta: f64, gamma: f64| {
+                (1.0 + a * (b / cos_theta).exp())
+                    * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+            };
+            self.zenith[i] * perez(cos_theta, gamma) / perez(1.0, theta_sun)
+        });
+        xyy_to_rgb(x, y, luminance)
+    }
+
+    // Light from the sky falling on a horizontal surface, integrated numerically.
+    fn sky_irradiance(&self) -> Colour {
+        const STEPS: usize = 32;
+        let mut irradiance = Colour::new(0.0, 0.0, 0.0);
+        for i in 0..STEPS {
+            let theta = 0.5 * PI * (i as f64 + 0.5) / STEPS as f64;
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            for j in 0..4 * STEPS {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / (4 * STEPS) as f64;
+                let direction =
+                    Vector::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+                let solid_angle =
+                    sin_theta * (0.5 * PI / STEPS as f64) * (2.0 * PI / (4 * STEPS) as f64);
+                irradiance += self.sky_radiance(direction) * (cos_theta * solid_angle);
+            }
+        }
+        irradiance
+    }
+
+    fn sun_irradiance(&self) -> Colour {
+        self.sun_colour * (self.sun_solid_angle() * self.sun_direction.y.max(0.0))
+    }
+
+    fn sun_solid_angle(&self) -> f64 {
+        2.0 * PI * (1.0 - self.cos_sun_radius)
+    }
+}
+
+impl Environment for PreethamSky {
+    fn value(&self, direction: Vector) -> Colour {
+        let direction = direction.normalize();
+        if direction.y < 0.0 {
+            return self.ground.mul_element_wise(self.irradiance) * (self.scale() / PI);
+        }
+
+        let mut radiance = self.sky_radiance(direction);
+        if dot(direction, self.sun_direction) >= self.cos_sun_radius {
+            radiance += self.sun_colour;
+        }
+        radiance * self.scale()
+    }
+
+    fn is_sampled(&self) -> bool {
+        true
+    }
+
+    // Directions are drawn from the sun disk half the time and uniformly from the
+    // whole sphere otherwise.
+    fn pdf_value(&self, direction: Vector) -> f64 {
+        let sphere_pdf = 1.0 / (4.0 * PI);
+        let sun_pdf = match dot(direction.normalize(), self.sun_direction) >= self.cos_sun_radius {
+            true => 1.0 / self.sun_solid_angle(),
+            false => 0.0,
+        };
+        SUN_SAMPLE_PROBABILITY * sun_pdf + (1.0 - SUN_SAMPLE_PROBABILITY) * sphere_pdf
+    }
+
+    fn random(&self) -> Vector {
+        if random::<f64>() >= SUN_SAMPLE_PROBABILITY {
+            return random_unit_vector();
+        }
+
+        let z = 1.0 + random::<f64>() * (self.cos_sun_radius - 1.0);
+        let phi = 2.0 * PI * random::<f64>();
+        let sin_theta = (1.0 - z * z).sqrt();
+
+        Onb::new(self.sun_direction).to_world(Vector::new(
+            phi.cos() * sin_theta,
+            phi.sin() * sin_theta,
+            z,
+        ))
+    }
+}
+
+// The sun's radiance after Rayleigh and aerosol extinction along its path through the
+// air, using Preetham et al.'s transmittance formulas at a wavelength for each channel.
+fn sun_colour(theta_sun: f64, turbidity: f64) -> Colour {
+    let relative_air_mass =
+        1.0 / (theta_sun.cos() + 0.15 * (93.885 - theta_sun.to_degrees()).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let transmittance = |wavelength: f64| {
+        let rayleigh = 0.008735 * wavelength.powf(-4.08);
+        let aerosol = beta * wavelength.powf(-1.3);
+        (-(rayleigh + aerosol) * relative_air_mass).exp()
+    };
+    Colour::new(
+        transmittance(0.65),
+        transmittance(0.55),
+        transmittance(0.45),
+    ) * SUN_LUMINANCE
+}
+
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Colour {
+    if y <= 0.0 {
+        return Colour::new(0.0, 0.0, 0.0);
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Colour::new(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
+
+fn luminance(colour: Colour) -> f64 {
+    0.2126 * colour.x + 0.7152 * colour.y + 0.0722 * colour.z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng;
+
+    fn clear_sky(sun_direction: Vector) -> PreethamSky {
+        PreethamSky::new(sun_direction, 2.5, Colour::new(0.3, 0.3, 0.3))
+    }
+
+    #[test]
+    fn a_high_sun_lights_the_zenith_more_than_the_horizon() {
+        let sky = clear_sky(Vector::new(0.3, 1.0, 0.0));
+        let zenith = luminance(sky.value(Vector::new(0.0, 1.0, 0.0)));
+        // Just above the horizon, on the side away from the sun.
+        let horizon = luminance(sky.value(Vector::new(-1.0, 0.03, 0.0)));
+        assert!(zenith > horizon, "zenith {zenith}, horizon {horizon}");
+        assert!(horizon > 0.0);
+    }
+
+    #[test]
+    fn the_sampling_density_integrates_to_one() {
+        rng::seed(6);
+        const SAMPLES: usize = 100_000;
+        // A wide sun, so that uniform directions land on it often enough to count.
+        let sky = clear_sky(Vector::new(0.3, 1.0, 0.0)).with_sun_angle(60.0);
+        let integral = (0..SAMPLES)
+            .map(|_| sky.pdf_value(random_unit_vector()) * 4.0 * PI)
+            .sum::<f64>()
+            / SAMPLES as f64;
+        assert!((integral - 1.0).abs() < 0.02, "{integral}");
+
+        // With the real sun, directions drawn by random cover the sphere's 4π.
+        let sky = clear_sky(Vector::new(0.3, 1.0, 0.0));
+        let area = (0..SAMPLES)
+            .map(|_| 1.0 / sky.pdf_value(sky.random()))
+            .sum::<f64>()
+            / SAMPLES as f64;
+        assert!((area / (4.0 * PI) - 1.0).abs() < 0.02, "{area}");
+    }
+}
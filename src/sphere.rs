@@ -1,11 +1,13 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::{
-    hittable::{HitRecord, Hittable},
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, Span, area_to_solid_angle_pdf, spans_from_hits},
     interval::Interval,
-    material::Material,
+    material::{Material, random_unit_vector},
     ray::Ray,
-    vector::{Point, dot},
+    rng::random,
+    vector::{Onb, Point, Vector, dot},
 };
 
 pub struct Sphere {
@@ -26,37 +28,164 @@ impl Sphere {
 
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
-        let oc = self.centre - ray.origin;
-        let a = ray.direction.magnitude2();
-        let h = dot(ray.direction, oc);
-        let c = oc.magnitude2() - self.radius * self.radius;
+        hit_sphere(ray, ray_t, self.centre, self.radius, &self.material)
+    }
 
-        let discrimant = h * h - a * c;
+    fn bounding_box(&self) -> Aabb {
+        let radius_vec = Vector::new(self.radius, self.radius, self.radius);
+        Aabb::from_points(self.centre - radius_vec, self.centre + radius_vec)
+    }
 
-        if discrimant < 0.0 {
-            return None;
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        let Some(rec) = self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) else {
+            return 0.0;
+        };
+
+        let distance_squared = (self.centre - origin).magnitude2();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            let area = 4.0 * PI * radius_squared;
+            return area_to_solid_angle_pdf(1.0 / area, &rec, direction);
         }
 
-        let sqrt_discriminant = discrimant.sqrt();
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    // Samples the cone of directions the sphere covers from outside, or the whole
+    // surface from inside.
+    fn random(&self, origin: Point) -> Vector {
+        let direction = self.centre - origin;
+        let distance_squared = direction.magnitude2();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return self.centre + random_unit_vector() * self.radius - origin;
+        }
+
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let r1: f64 = random();
+        let r2: f64 = random();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z * z).sqrt();
+
+        Onb::new(direction).to_world(Vector::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
 
-        let mut root = (h - sqrt_discriminant) / a;
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        Some(spans_from_hits(self, ray))
+    }
+}
+
+// A sphere moving in a straight line from centre0 at time 0 to centre1 at time 1,
+// staying at either end outside those times.
+pub struct MovingSphere {
+    pub centre0: Point,
+    pub centre1: Point,
+    pub radius: f64,
+    pub material: Arc<Box<dyn Material + Sync + Send>>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        centre0: Point,
+        centre1: Point,
+        radius: f64,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        Self {
+            centre0,
+            centre1,
+            radius,
+            material,
+        }
+    }
+
+    pub fn centre(&self, time: f64) -> Point {
+        self.centre0 + (self.centre1 - self.centre0) * time.clamp(0.0, 1.0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        hit_sphere(
+            ray,
+            ray_t,
+            self.centre(ray.time),
+            self.radius,
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius_vec = Vector::new(self.radius, self.radius, self.radius);
+        Aabb::enclosing(
+            &Aabb::from_points(self.centre0 - radius_vec, self.centre0 + radius_vec),
+            &Aabb::from_points(self.centre1 - radius_vec, self.centre1 + radius_vec),
+        )
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        Some(spans_from_hits(self, ray))
+    }
+}
+
+fn hit_sphere(
+    ray: &Ray,
+    ray_t: Interval,
+    centre: Point,
+    radius: f64,
+    material: &Arc<Box<dyn Material + Sync + Send>>,
+) -> Option<HitRecord> {
+    let oc = centre - ray.origin;
+    let a = ray.direction.magnitude2();
+    let h = dot(ray.direction, oc);
+    let c = oc.magnitude2() - radius * radius;
+
+    let discrimant = h * h - a * c;
+
+    if discrimant < 0.0 {
+        return None;
+    }
+
+    let sqrt_discriminant = discrimant.sqrt();
+
+    let mut root = (h - sqrt_discriminant) / a;
+    if !ray_t.surounds(root) {
+        root = (h + sqrt_discriminant) / a;
         if !ray_t.surounds(root) {
-            root = (h + sqrt_discriminant) / a;
-            if !ray_t.surounds(root) {
-                return None;
-            }
+            return None;
         }
-        let point = ray.at(root);
-        let mut normal = (point - self.centre) / self.radius;
-        let front_face = dot(ray.direction, normal) < 0.0;
-        normal = if front_face { normal } else { -normal };
-
-        Some(HitRecord {
-            point,
-            normal,
-            t: root,
-            front_face,
-            material: self.material.clone(),
-        })
     }
+    let point = ray.at(root);
+    let mut normal = (point - centre) / radius;
+    let front_face = dot(ray.direction, normal) < 0.0;
+    let (u, v) = sphere_uv(normal);
+    normal = if front_face { normal } else { -normal };
+
+    Some(HitRecord {
+        point,
+        normal,
+        t: root,
+        u,
+        v,
+        front_face,
+        material: material.clone(),
+    })
+}
+
+// Maps a point on the unit sphere to (u, v), with u going around the y axis from
+// x = -1 and v going from the bottom pole to the top.
+pub(crate) fn sphere_uv(point: Point) -> (f64, f64) {
+    let theta = (-point.y).acos();
+    let phi = (-point.z).atan2(point.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
 }
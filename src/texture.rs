@@ -0,0 +1,280 @@
+use std::{path::Path, sync::Arc};
+
+use image::{ImageFormat, ImageResult};
+
+use crate::{Colour, gamma_to_linear, perlin::Perlin, vector::Point};
+
+pub trait Texture: Sync + Send {
+    fn value(&self, u: f64, v: f64, point: Point) -> Colour;
+}
+
+pub struct SolidColour {
+    albedo: Colour,
+}
+
+impl SolidColour {
+    pub fn new(albedo: Colour) -> Self {
+        SolidColour { albedo }
+    }
+}
+
+impl Texture for SolidColour {
+    fn value(&self, _u: f64, _v: f64, _point: Point) -> Colour {
+        self.albedo
+    }
+}
+
+// Alternates between two textures in a 3D grid of cubes with sides of length scale.
+pub struct Checker {
+    inv_scale: f64,
+    even: Arc<Box<dyn Texture>>,
+    odd: Arc<Box<dyn Texture>>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, even: Arc<Box<dyn Texture>>, odd: Arc<Box<dyn Texture>>) -> Self {
+        Checker {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colours(scale: f64, even: Colour, odd: Colour) -> Self {
+        Checker::new(
+            scale,
+            Arc::new(Box::new(SolidColour::new(even))),
+            Arc::new(Box::new(SolidColour::new(odd))),
+        )
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, point: Point) -> Colour {
+        let x = (self.inv_scale * point.x).floor() as i64;
+        let y = (self.inv_scale * point.y).floor() as i64;
+        let z = (self.inv_scale * point.z).floor() as i64;
+
+        match (x + y + z) % 2 == 0 {
+            true => self.even.value(u, v, point),
+            false => self.odd.value(u, v, point),
+        }
+    }
+}
+
+// How texture coordinates outside [0, 1] are mapped back onto the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    Clamp,
+}
+
+impl WrapMode {
+    fn apply(self, texel: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => texel.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let period = texel.rem_euclid(2 * size);
+                match period < size {
+                    true => period,
+                    false => 2 * size - 1 - period,
+                }
+            }
+            WrapMode::Clamp => texel.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // Linear colours, row by row from the top of the image.
+    texels: Vec<Colour>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn load(path: impl AsRef<Path>, wrap: WrapMode) -> ImageResult<Self> {
+        let (width, height, texels) = load_linear(path.as_ref())?;
+        Ok(ImageTexture {
+            width,
+            height,
+            texels,
+            wrap,
+        })
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Colour {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        self.texels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    // Bilinearly filters the four texels around (u, v). v runs from the bottom of
+    // the image to the top.
+    fn value(&self, u: f64, v: f64, _point: Point) -> Colour {
+        if self.texels.is_empty() {
+            return Colour::new(0.0, 1.0, 1.0);
+        }
+
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x0 + 1, y0) * tx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - tx) + self.texel(x0 + 1, y0 + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+// Decodes an image into linear colours, row by row from the top, along with its width
+// and height. HDR and OpenEXR images are linear already; anything else is taken to be
+// gamma encoded.
+pub(crate) fn load_linear(path: &Path) -> ImageResult<(usize, usize, Vec<Colour>)> {
+    let linear = matches!(
+        ImageFormat::from_path(path),
+        Ok(ImageFormat::Hdr | ImageFormat::OpenExr)
+    );
+    let image = image::open(path)?.into_rgb32f();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let texels = image
+        .pixels()
+        .map(|pixel| {
+            let colour = Colour::new(
+                f64::from(pixel[0]),
+                f64::from(pixel[1]),
+                f64::from(pixel[2]),
+            );
+            match linear {
+                true => colour,
+                false => Colour::new(
+                    gamma_to_linear(colour.x),
+                    gamma_to_linear(colour.y),
+                    gamma_to_linear(colour.z),
+                ),
+            }
+        })
+        .collect();
+    Ok((width, height, texels))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseStyle {
+    // Plain Perlin noise.
+    Smooth,
+    // Several octaves of noise, giving a cloudy look.
+    Turbulence,
+    // Turbulence used to phase shift a sine wave, giving veins like marble.
+    Marble,
+}
+
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+    style: NoiseStyle,
+    albedo: Colour,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, style: NoiseStyle, albedo: Colour) -> Self {
+        NoiseTexture {
+            noise: Perlin::new(),
+            scale,
+            style,
+            albedo,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: Point) -> Colour {
+        let intensity = match self.style {
+            NoiseStyle::Smooth => 0.5 * (1.0 + self.noise.noise(point * self.scale)),
+            NoiseStyle::Turbulence => self.noise.turbulence(point * self.scale, 7),
+            NoiseStyle::Marble => {
+                0.5 * (1.0 + (self.scale * point.z + 10.0 * self.noise.turbulence(point, 7)).sin())
+            }
+        };
+        self.albedo * intensity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, Rgb32FImage, RgbImage};
+    use std::{env, fs};
+
+    // Four texels across, of brightness 0, 1, 2 and 3.
+    fn ramp(wrap: WrapMode) -> ImageTexture {
+        ImageTexture {
+            width: 4,
+            height: 1,
+            texels: (0..4)
+                .map(|i| Colour::new(1.0, 1.0, 1.0) * f64::from(i))
+                .collect(),
+            wrap,
+        }
+    }
+
+    #[test]
+    fn wrap_modes_map_coordinates_back_onto_the_image() {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        // A quarter of the width past either edge falls halfway between two texels.
+        for (wrap, before, after) in [
+            (WrapMode::Repeat, 2.5, 0.5),
+            (WrapMode::MirroredRepeat, 0.5, 2.5),
+            (WrapMode::Clamp, 0.0, 3.0),
+        ] {
+            let texture = ramp(wrap);
+            assert_eq!(texture.value(-0.25, 0.5, origin).x, before, "{wrap:?}");
+            assert_eq!(texture.value(1.25, 0.5, origin).x, after, "{wrap:?}");
+        }
+    }
+
+    #[test]
+    fn checkers_alternate_across_every_face() {
+        let white = Colour::new(1.0, 1.0, 1.0);
+        let black = Colour::new(0.0, 0.0, 0.0);
+        let checker = Checker::from_colours(2.0, white, black);
+        for (point, expected) in [
+            (Point::new(1.0, 1.0, 1.0), white),
+            (Point::new(3.0, 1.0, 1.0), black),
+            (Point::new(3.0, 3.0, 1.0), white),
+            (Point::new(-1.0, 1.0, 1.0), black),
+            (Point::new(-1.0, -1.0, 1.0), white),
+            (Point::new(-1.0, -1.0, -1.0), black),
+        ] {
+            assert_eq!(checker.value(0.0, 0.0, point), expected, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn only_low_dynamic_range_images_are_gamma_decoded() {
+        let directory = env::temp_dir().join(format!("ray-tracing-texture-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let hdr = directory.join("linear.hdr");
+        let png = directory.join("gamma.png");
+        Rgb32FImage::from_pixel(1, 1, Rgb([0.25, 0.5, 2.0]))
+            .save(&hdr)
+            .unwrap();
+        RgbImage::from_pixel(1, 1, Rgb([51, 102, 255]))
+            .save(&png)
+            .unwrap();
+
+        let (hdr_texels, png_texels) = (load_linear(&hdr), load_linear(&png));
+        fs::remove_dir_all(&directory).unwrap();
+
+        let (_, _, texels) = hdr_texels.unwrap();
+        assert!(texels[0].approx_eq(&Colour::new(0.25, 0.5, 2.0), 1e-2));
+        let (_, _, texels) = png_texels.unwrap();
+        assert!(texels[0].approx_eq(&Colour::new(0.04, 0.16, 1.0), 1e-6));
+    }
+}
@@ -0,0 +1,187 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    disk::disk_bounding_box,
+    hittable::{HitRecord, Hittable, Span, oriented_hit_record, spans_from_hits},
+    interval::Interval,
+    material::Material,
+    polynomial::{solve_quadratic, solve_quartic},
+    ray::Ray,
+    vector::{Onb, Point, Vector, dot},
+};
+
+// A ring around axis through centre: the points minor_radius away from a circle of
+// major_radius. u runs once around the axis and v once around the tube, starting
+// from its outside edge.
+pub struct Torus {
+    centre: Point,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Arc<Box<dyn Material + Sync + Send>>,
+    // The axis runs along w.
+    onb: Onb,
+}
+
+impl Torus {
+    pub fn new(
+        centre: Point,
+        axis: Vector,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        Self {
+            centre,
+            major_radius,
+            minor_radius,
+            material,
+            onb: Onb::new(axis),
+        }
+    }
+}
+
+impl Hittable for Torus {
+    // Solves (|p|² + R² - r²)² = 4R²(x² + y²) with the axis along z. To keep the
+    // quartic well conditioned the ray is first moved to the point nearest the centre
+    // and given a unit direction, and rays that miss the bounding sphere are skipped.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let length = ray.direction.magnitude();
+        let direction = self.onb.to_local(ray.direction) / length;
+        let origin = self.onb.to_local(ray.origin - self.centre);
+
+        let outer_radius = self.major_radius + self.minor_radius;
+        let bounds = solve_quadratic(
+            1.0,
+            2.0 * dot(origin, direction),
+            origin.magnitude2() - outer_radius * outer_radius,
+        );
+        let [enter, exit] = bounds[..] else {
+            return None;
+        };
+        if exit / length <= ray_t.min || enter / length >= ray_t.max {
+            return None;
+        }
+
+        let shift = -dot(origin, direction);
+        let origin = origin + shift * direction;
+
+        let r2 = self.major_radius * self.major_radius;
+        let e = origin.magnitude2() + r2 - self.minor_radius * self.minor_radius;
+        let f = dot(origin, direction);
+        let planar_dot = origin.x * direction.x + origin.y * direction.y;
+        let planar_origin = origin.x * origin.x + origin.y * origin.y;
+        let planar_direction = direction.x * direction.x + direction.y * direction.y;
+        let roots = solve_quartic(
+            4.0 * f,
+            4.0 * f * f + 2.0 * e - 4.0 * r2 * planar_direction,
+            4.0 * e * f - 8.0 * r2 * planar_dot,
+            e * e - 4.0 * r2 * planar_origin,
+        );
+
+        let (s, t) = roots
+            .into_iter()
+            .map(|s| (s, (s + shift) / length))
+            .find(|&(_, t)| ray_t.surounds(t))?;
+        let point = origin + s * direction;
+
+        // The normal points away from the nearest point on the circle through the tube.
+        let planar_distance = (point.x * point.x + point.y * point.y).sqrt();
+        let ring_point = match planar_distance > 0.0 {
+            true => Vector::new(point.x, point.y, 0.0) * (self.major_radius / planar_distance),
+            false => Vector::new(self.major_radius, 0.0, 0.0),
+        };
+        let outward_normal = self.onb.to_world(point - ring_point).normalize();
+
+        let u = (point.y.atan2(point.x) + PI) / (2.0 * PI);
+        let tube_angle = point.z.atan2(planar_distance - self.major_radius);
+        let v = tube_angle.rem_euclid(2.0 * PI) / (2.0 * PI);
+        Some(oriented_hit_record(
+            ray,
+            t,
+            outward_normal,
+            (u, v),
+            &self.material,
+        ))
+    }
+
+    // The centre circle's box grown by the tube's radius on every side.
+    fn bounding_box(&self) -> Aabb {
+        let ring = disk_bounding_box(self.centre, self.onb.w, self.major_radius);
+        Aabb::new(
+            ring.x.expand(2.0 * self.minor_radius),
+            ring.y.expand(2.0 * self.minor_radius),
+            ring.z.expand(2.0 * self.minor_radius),
+        )
+    }
+
+    fn spans(&self, ray: &Ray) -> Option<Vec<Span>> {
+        Some(spans_from_hits(self, ray))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Colour, material::Lambertian};
+
+    // Lying flat around the y axis, with a tube half a unit thick two units out.
+    fn torus() -> Torus {
+        let material: Arc<Box<dyn Material + Sync + Send>> =
+            Arc::new(Box::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))));
+        Torus::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            2.0,
+            0.5,
+            material,
+        )
+    }
+
+    fn hit(ray: &Ray, min: f64) -> Option<HitRecord> {
+        torus().hit(ray, Interval::new(min, f64::INFINITY))
+    }
+
+    #[test]
+    fn rays_along_the_axis_pass_through_the_hole() {
+        let ray = Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(hit(&ray, 0.001).is_none());
+    }
+
+    #[test]
+    fn the_nearest_of_four_roots_is_hit() {
+        // Crosses the tube at x = -2.5, -1.5, 1.5 and 2.5.
+        let ray = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let expected = [(2.5, true), (3.5, false), (6.5, true), (7.5, false)];
+        let mut min = 0.001;
+        for (t, front_face) in expected {
+            let rec = hit(&ray, min).unwrap_or_else(|| panic!("missed the root at {t}"));
+            assert!((rec.t - t).abs() < 1e-6, "hit at {}, not {t}", rec.t);
+            assert_eq!(rec.front_face, front_face);
+            // Across the middle of the tube the normal points straight along the ray.
+            assert!(rec.normal.approx_eq(&Vector::new(-1.0, 0.0, 0.0), 1e-6));
+            min = t + 0.1;
+        }
+        assert!(hit(&ray, min).is_none());
+    }
+
+    #[test]
+    fn normals_point_out_of_the_tube() {
+        let ray = Ray::new(Point::new(2.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let rec = hit(&ray, 0.001).expect("ray should hit the top of the tube");
+        assert!((rec.t - 4.5).abs() < 1e-6);
+        assert!(rec.front_face);
+        assert!(rec.normal.approx_eq(&Vector::new(0.0, 1.0, 0.0), 1e-6));
+
+        // 45 degrees around the tube from its outer edge.
+        let offset = 0.5 * std::f64::consts::FRAC_1_SQRT_2;
+        let ray = Ray::new(
+            Point::new(2.0 + offset, 5.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        let rec = hit(&ray, 0.001).expect("ray should hit the side of the tube");
+        assert!((rec.t - (5.0 - offset)).abs() < 1e-6);
+        let outward = Vector::new(1.0, 1.0, 0.0).normalize();
+        assert!(rec.normal.approx_eq(&outward, 1e-6));
+    }
+}
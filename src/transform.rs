@@ -0,0 +1,217 @@
+use crate::{
+    aabb::Aabb,
+    vector::{Matrix3, Matrix4, Point, Quaternion, Vector, dot},
+};
+
+// An affine transformation as a 4x4 matrix, kept together with its inverse so that
+// rays can be taken into an object's space and hits brought back out cheaply.
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        matrix: Matrix4::IDENTITY,
+        inverse: Matrix4::IDENTITY,
+    };
+
+    // None if the matrix has no inverse.
+    pub fn from_matrix(matrix: Matrix4) -> Option<Self> {
+        Some(Transform {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(offset: Vector) -> Self {
+        Transform {
+            matrix: Matrix4::from_linear(Matrix3::IDENTITY, offset),
+            inverse: Matrix4::from_linear(Matrix3::IDENTITY, -offset),
+        }
+    }
+
+    // Panics if any factor is zero, which would flatten objects and has no inverse.
+    pub fn scale(factors: Vector) -> Self {
+        assert!(
+            factors.x != 0.0 && factors.y != 0.0 && factors.z != 0.0,
+            "scale factors must be non-zero"
+        );
+        let diagonal =
+            |d: Vector| Matrix3::new([[d.x, 0.0, 0.0], [0.0, d.y, 0.0], [0.0, 0.0, d.z]]);
+        let zero = Vector::new(0.0, 0.0, 0.0);
+        Transform {
+            matrix: Matrix4::from_linear(diagonal(factors), zero),
+            inverse: Matrix4::from_linear(diagonal(Vector::new(1.0, 1.0, 1.0) / factors), zero),
+        }
+    }
+
+    // Rotates anticlockwise about axis, looking down it towards the origin.
+    pub fn rotate(axis: Vector, degrees: f64) -> Self {
+        Transform::rotation(Quaternion::from_axis_angle(axis, degrees))
+    }
+
+    pub fn rotation(rotation: Quaternion) -> Self {
+        Transform::from_rotation_matrix(rotation.normalize().to_matrix())
+    }
+
+    // The inverse of a rotation is its transpose.
+    fn from_rotation_matrix(rotation: Matrix3) -> Self {
+        let zero = Vector::new(0.0, 0.0, 0.0);
+        Transform {
+            matrix: Matrix4::from_linear(rotation, zero),
+            inverse: Matrix4::from_linear(rotation.transpose(), zero),
+        }
+    }
+
+    // Places an object at from with its +z axis pointing towards to, and its +y
+    // axis as close to up as possible.
+    pub fn look_at(from: Point, to: Point, up: Vector) -> Self {
+        let w = (to - from).normalize();
+        let u = up.cross(w).normalize();
+        let v = w.cross(u);
+
+        Transform::from_rotation_matrix(Matrix3::from_columns(u, v, w))
+            .then(Transform::translate(from))
+    }
+
+    // This transformation followed by next.
+    pub fn then(&self, next: Transform) -> Transform {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn point(&self, point: Point) -> Point {
+        self.matrix.transform_point(point)
+    }
+
+    pub fn vector(&self, vector: Vector) -> Vector {
+        self.matrix.transform_vector(vector)
+    }
+
+    // Normals stay perpendicular to surfaces by using the inverse transpose. The
+    // result is not normalised.
+    pub fn normal(&self, normal: Vector) -> Vector {
+        self.inverse.linear().transpose() * normal
+    }
+
+    // Whether the transformation keeps angles, so that it is only made up of
+    // rotations, reflections, translations and uniform scaling.
+    pub fn preserves_angles(&self) -> bool {
+        let linear = self.matrix.linear();
+        let columns = [0, 1, 2].map(|c| linear.column(c));
+        let length = columns[0].magnitude2();
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * length;
+        columns
+            .iter()
+            .all(|column| close(column.magnitude2(), length))
+            && close(dot(columns[0], columns[1]), 0.0)
+            && close(dot(columns[1], columns[2]), 0.0)
+            && close(dot(columns[0], columns[2]), 0.0)
+    }
+
+    // The box enclosing the transformed corners of bbox. Unbounded boxes stay unbounded.
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        if !bbox.is_bounded() {
+            return Aabb::UNIVERSE;
+        }
+        let mut min = Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = -min;
+        for corner in bbox.corners() {
+            let point = self.point(corner);
+            min = min.min(point);
+            max = max.max(point);
+        }
+        Aabb::from_points(min, max)
+    }
+}
+
+// A transform split into a scale, which may stretch along any axes, then a rotation
+// and then a translation. Each part can be interpolated on its own, so that moving
+// between two transforms turns objects rather than squashing them through the
+// middle of a rotation.
+#[derive(Clone, Copy, Debug)]
+pub struct Decomposed {
+    pub scale: Matrix3,
+    pub rotation: Quaternion,
+    pub translation: Vector,
+}
+
+impl Transform {
+    // The linear part is split into a rotation and a symmetric stretch by polar
+    // decomposition, averaging the matrix with its inverse transpose until only the
+    // rotation is left. A reflection is kept in the stretch, so that the rotation is
+    // always a proper one.
+    pub fn decompose(&self) -> Decomposed {
+        let linear = self.matrix.linear();
+        let mut rotation = linear;
+        for _ in 0..100 {
+            let Some(inverse) = rotation.inverse() else {
+                break;
+            };
+            let next = (rotation + inverse.transpose()) * 0.5;
+            let converged = next.approx_eq(&rotation, 1e-14);
+            rotation = next;
+            if converged {
+                break;
+            }
+        }
+        if rotation.determinant() < 0.0 {
+            rotation = rotation * -1.0;
+        }
+        let matrix = self.matrix;
+        Decomposed {
+            scale: rotation.transpose() * linear,
+            rotation: Quaternion::from_matrix(rotation),
+            translation: Vector::new(matrix.rows[0][3], matrix.rows[1][3], matrix.rows[2][3]),
+        }
+    }
+}
+
+impl Decomposed {
+    // Between self at t = 0 and other at t = 1: the rotation turns at a constant rate
+    // and the translation and scale change linearly.
+    pub fn lerp(&self, other: &Decomposed, t: f64) -> Decomposed {
+        Decomposed {
+            scale: self.scale * (1.0 - t) + other.scale * t,
+            rotation: self.rotation.slerp(other.rotation, t),
+            translation: self.translation + (other.translation - self.translation) * t,
+        }
+    }
+
+    // Whether the scale mirrors objects. Interpolating between a transform that does
+    // and one that does not would flatten objects part way.
+    pub fn is_reflection(&self) -> bool {
+        self.scale.determinant() < 0.0
+    }
+
+    // The parts put back together. None if the scale has been flattened.
+    pub fn transform(&self) -> Option<Transform> {
+        let rotation = self.rotation.normalize().to_matrix();
+        let inverse_linear = self.scale.inverse()? * rotation.transpose();
+        Some(Transform {
+            matrix: Matrix4::from_linear(rotation * self.scale, self.translation),
+            inverse: Matrix4::from_linear(inverse_linear, -(inverse_linear * self.translation)),
+        })
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, area_to_solid_angle_pdf},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    rng::random,
+    vector::{Point, Vector, dot},
+};
+
+pub struct Triangle {
+    pub vertices: [Point; 3],
+    pub normals: Option<[Vector; 3]>,
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub material: Arc<Box<dyn Material + Sync + Send>>,
+}
+
+impl Triangle {
+    pub fn new(
+        a: Point,
+        b: Point,
+        c: Point,
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+
+    // Per-vertex normals are interpolated across the face for smooth shading.
+    pub fn with_normals(
+        vertices: [Point; 3],
+        normals: [Vector; 3],
+        material: Arc<Box<dyn Material + Sync + Send>>,
+    ) -> Self {
+        Self {
+            vertices,
+            normals: Some(normals),
+            uvs: None,
+            material,
+        }
+    }
+
+    // Without texture coordinates the barycentric coordinates are used instead.
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        hit_triangle(
+            ray,
+            ray_t,
+            self.vertices,
+            self.normals,
+            self.uvs,
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        triangle_bounding_box(self.vertices)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: Vector) -> f64 {
+        match self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) {
+            Some(rec) => {
+                area_to_solid_angle_pdf(1.0 / triangle_area(self.vertices), &rec, direction)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: Point) -> Vector {
+        random_triangle_point(self.vertices) - origin
+    }
+}
+
+// Möller–Trumbore intersection. Returns the distance along the ray and the barycentric
+// weights of the second and third vertices.
+pub(crate) fn intersect(
+    ray: &Ray,
+    ray_t: Interval,
+    vertices: [Point; 3],
+) -> Option<(f64, f64, f64)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+
+    let p = ray.direction.cross(edge2);
+    let determinant = dot(edge1, p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+
+    let s = ray.origin - vertices[0];
+    let u = dot(s, p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = dot(ray.direction, q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = dot(edge2, q) * inverse_determinant;
+    if !ray_t.surounds(t) {
+        return None;
+    }
+    Some((t, u, v))
+}
+
+pub(crate) fn hit_triangle(
+    ray: &Ray,
+    ray_t: Interval,
+    vertices: [Point; 3],
+    normals: Option<[Vector; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    material: &Arc<Box<dyn Material + Sync + Send>>,
+) -> Option<HitRecord> {
+    let (t, u, v) = intersect(ray, ray_t, vertices)?;
+
+    let geometric_normal = (vertices[1] - vertices[0])
+        .cross(vertices[2] - vertices[0])
+        .normalize();
+    let front_face = dot(ray.direction, geometric_normal) < 0.0;
+
+    let mut normal = match normals {
+        Some([n0, n1, n2]) => {
+            let shading_normal = ((1.0 - u - v) * n0 + u * n1 + v * n2).normalize();
+            // Keep the shading normal on the same side of the surface as the geometry.
+            match dot(shading_normal, geometric_normal) < 0.0 {
+                true => -shading_normal,
+                false => shading_normal,
+            }
+        }
+        None => geometric_normal,
+    };
+    if !front_face {
+        normal = -normal;
+    }
+
+    let (tex_u, tex_v) = match uvs {
+        Some([uv0, uv1, uv2]) => (
+            (1.0 - u - v) * uv0.0 + u * uv1.0 + v * uv2.0,
+            (1.0 - u - v) * uv0.1 + u * uv1.1 + v * uv2.1,
+        ),
+        None => (u, v),
+    };
+
+    Some(HitRecord {
+        point: ray.at(t),
+        normal,
+        t,
+        u: tex_u,
+        v: tex_v,
+        front_face,
+        material: material.clone(),
+    })
+}
+
+pub(crate) fn triangle_bounding_box(vertices: [Point; 3]) -> Aabb {
+    Aabb::enclosing(
+        &Aabb::from_points(vertices[0], vertices[1]),
+        &Aabb::from_points(vertices[2], vertices[2]),
+    )
+}
+
+pub(crate) fn triangle_area(vertices: [Point; 3]) -> f64 {
+    0.5 * (vertices[1] - vertices[0])
+        .cross(vertices[2] - vertices[0])
+        .magnitude()
+}
+
+// Uniformly distributed over the triangle's area.
+pub(crate) fn random_triangle_point(vertices: [Point; 3]) -> Point {
+    let r1: f64 = random();
+    let r2: f64 = random();
+    let sqrt_r1 = r1.sqrt();
+    let u = 1.0 - sqrt_r1;
+    let v = r2 * sqrt_r1;
+    vertices[0] * (1.0 - u - v) + vertices[1] * u + vertices[2] * v
+}
@@ -1,9 +1,17 @@
 use std::{
     f64,
     fmt::Debug,
-    ops::{Add, AddAssign, Div, Mul, Neg, Sub},
+    ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
+// Matrices are singular when inverting them divides by something this small relative
+// to their entries.
+const SINGULAR_TOLERANCE: f64 = 1e-12;
+
+// Vectors, matrices and quaternions whose components all differ by no more than this
+// compare as equal.
+pub const EPSILON: f64 = 1e-9;
+
 pub type Point = Vector;
 #[derive(Copy, Clone)]
 pub struct Vector {
@@ -44,6 +52,46 @@ impl Vector {
             z: self.x * other.y - self.y * other.x,
         }
     }
+
+    // Component-wise minimum.
+    pub fn min(self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        }
+    }
+
+    // Component-wise maximum.
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
+    pub fn min_component(self) -> f64 {
+        self.x.min(self.y).min(self.z)
+    }
+
+    pub fn max_component(self) -> f64 {
+        self.x.max(self.y).max(self.z)
+    }
+
+    // Whether every component is within epsilon of other's, for comparing the results
+    // of different calculations.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        (*self - *other).abs().max_component() <= epsilon
+    }
 }
 
 impl Neg for Vector {
@@ -82,6 +130,12 @@ impl AddAssign for Vector {
     }
 }
 
+impl SubAssign for Vector {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
 impl Sub for Vector {
     type Output = Self;
     fn sub(self, other: Self) -> Self {
@@ -104,6 +158,26 @@ impl Mul<f64> for Vector {
     }
 }
 
+impl MulAssign<f64> for Vector {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+// Component-wise, the same as mul_element_wise.
+impl Mul for Vector {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.mul_element_wise(rhs)
+    }
+}
+
+impl MulAssign for Vector {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
 impl Mul<Vector> for f64 {
     type Output = Vector;
     fn mul(self, rhs: Vector) -> Vector {
@@ -126,6 +200,56 @@ impl Div<f64> for Vector {
     }
 }
 
+impl DivAssign<f64> for Vector {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
+
+// Component-wise.
+impl Div for Vector {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x / rhs.x,
+            y: self.y / rhs.y,
+            z: self.z / rhs.z,
+        }
+    }
+}
+
+// Components by axis: 0 for x, 1 for y and 2 for z.
+impl Index<usize> for Vector {
+    type Output = f64;
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("vector index {axis} out of range"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vector {
+    fn index_mut(&mut self, axis: usize) -> &mut f64 {
+        match axis {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("vector index {axis} out of range"),
+        }
+    }
+}
+
+// Approximate, so that results of different calculations can be compared: within
+// EPSILON in every component counts as equal. Use approx_eq for other tolerances.
+impl PartialEq for Vector {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, EPSILON)
+    }
+}
+
 impl Debug for Vector {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Vector")
@@ -135,3 +259,514 @@ impl Debug for Vector {
             .finish()
     }
 }
+
+// A 3x3 matrix stored row by row.
+#[derive(Copy, Clone, Debug)]
+pub struct Matrix3 {
+    pub rows: [[f64; 3]; 3],
+}
+
+impl Matrix3 {
+    pub const IDENTITY: Matrix3 = Matrix3 {
+        rows: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    pub fn new(rows: [[f64; 3]; 3]) -> Self {
+        Matrix3 { rows }
+    }
+
+    pub fn from_columns(a: Vector, b: Vector, c: Vector) -> Self {
+        Matrix3::new([[a.x, b.x, c.x], [a.y, b.y, c.y], [a.z, b.z, c.z]])
+    }
+
+    pub fn row(&self, row: usize) -> Vector {
+        let [x, y, z] = self.rows[row];
+        Vector::new(x, y, z)
+    }
+
+    pub fn column(&self, column: usize) -> Vector {
+        Vector::new(
+            self.rows[0][column],
+            self.rows[1][column],
+            self.rows[2][column],
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        Matrix3::from_columns(self.row(0), self.row(1), self.row(2))
+    }
+
+    pub fn determinant(&self) -> f64 {
+        dot(self.row(0), self.row(1).cross(self.row(2)))
+    }
+
+    // Whether every entry is within epsilon of other's.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        (0..3).all(|r| self.row(r).approx_eq(&other.row(r), epsilon))
+    }
+
+    // None if the matrix is singular. The determinant is compared with the product of
+    // the lengths of the rows, which bounds it, so that the test does not depend on
+    // the scale of the matrix.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        let bound = self.row(0).magnitude() * self.row(1).magnitude() * self.row(2).magnitude();
+        if !determinant.is_finite() || determinant.abs() <= SINGULAR_TOLERANCE * bound {
+            return None;
+        }
+        // The rows of the inverse times the determinant are the cross products of
+        // pairs of columns.
+        let (a, b, c) = (self.column(0), self.column(1), self.column(2));
+        let adjugate = Matrix3::from_columns(b.cross(c), c.cross(a), a.cross(b)).transpose();
+        Some(adjugate * (1.0 / determinant))
+    }
+}
+
+// Within EPSILON in every entry, as for vectors.
+impl PartialEq for Matrix3 {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, EPSILON)
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut rows = [[0.0; 3]; 3];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = dot(self.row(r), rhs.column(c));
+            }
+        }
+        Matrix3::new(rows)
+    }
+}
+
+impl Mul<Vector> for Matrix3 {
+    type Output = Vector;
+    fn mul(self, rhs: Vector) -> Vector {
+        Vector::new(
+            dot(self.row(0), rhs),
+            dot(self.row(1), rhs),
+            dot(self.row(2), rhs),
+        )
+    }
+}
+
+impl Add for Matrix3 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let mut rows = self.rows;
+        for (row, rhs_row) in rows.iter_mut().zip(rhs.rows) {
+            for (value, rhs_value) in row.iter_mut().zip(rhs_row) {
+                *value += rhs_value;
+            }
+        }
+        Matrix3::new(rows)
+    }
+}
+
+impl Mul<f64> for Matrix3 {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        Matrix3::new(self.rows.map(|row| row.map(|value| value * rhs)))
+    }
+}
+
+// A 4x4 matrix stored row by row, acting on points with an implicit w of 1 and on
+// directions with a w of 0.
+#[derive(Copy, Clone, Debug)]
+pub struct Matrix4 {
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4 {
+        rows: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Matrix4 { rows }
+    }
+
+    // A matrix that applies linear and then adds translation.
+    pub fn from_linear(linear: Matrix3, translation: Vector) -> Self {
+        let mut rows = Matrix4::IDENTITY.rows;
+        for (r, row) in rows.iter_mut().take(3).enumerate() {
+            row[..3].copy_from_slice(&linear.rows[r]);
+            row[3] = translation[r];
+        }
+        Matrix4::new(rows)
+    }
+
+    // The upper left 3x3 part, which is how directions are transformed.
+    pub fn linear(&self) -> Matrix3 {
+        Matrix3::new([0, 1, 2].map(|r| [self.rows[r][0], self.rows[r][1], self.rows[r][2]]))
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+        for (r, row) in self.rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                rows[c][r] = *value;
+            }
+        }
+        Matrix4::new(rows)
+    }
+
+    // Gauss-Jordan elimination with partial pivoting. None if the matrix is singular,
+    // which is when a pivot is tiny next to the largest entry of the matrix.
+    pub fn inverse(&self) -> Option<Self> {
+        let largest = self
+            .rows
+            .iter()
+            .flatten()
+            .fold(0.0, |a: f64, b| a.max(b.abs()));
+        if !largest.is_finite() {
+            return None;
+        }
+        let mut left = self.rows;
+        let mut right = Matrix4::IDENTITY.rows;
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&a, &b| left[a][column].abs().total_cmp(&left[b][column].abs()))
+                .unwrap_or(column);
+            if left[pivot][column].abs() <= SINGULAR_TOLERANCE * largest {
+                return None;
+            }
+            left.swap(column, pivot);
+            right.swap(column, pivot);
+
+            let scale = 1.0 / left[column][column];
+            for c in 0..4 {
+                left[column][c] *= scale;
+                right[column][c] *= scale;
+            }
+            for row in 0..4 {
+                let factor = left[row][column];
+                if row == column || factor == 0.0 {
+                    continue;
+                }
+                for c in 0..4 {
+                    left[row][c] -= factor * left[column][c];
+                    right[row][c] -= factor * right[column][c];
+                }
+            }
+        }
+        Some(Matrix4::new(right))
+    }
+
+    pub fn transform_point(&self, point: Point) -> Point {
+        self.linear() * point + Vector::new(self.rows[0][3], self.rows[1][3], self.rows[2][3])
+    }
+
+    pub fn transform_vector(&self, vector: Vector) -> Vector {
+        self.linear() * vector
+    }
+
+    // Whether every entry is within epsilon of other's.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.rows
+            .iter()
+            .flatten()
+            .zip(other.rows.iter().flatten())
+            .all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+}
+
+// Within EPSILON in every entry, as for vectors.
+impl PartialEq for Matrix4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, EPSILON)
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[r][k] * rhs.rows[k][c]).sum();
+            }
+        }
+        Matrix4::new(rows)
+    }
+}
+
+// A rotation as a unit quaternion, which can be smoothly interpolated.
+#[derive(Copy, Clone, Debug)]
+pub struct Quaternion {
+    pub w: f64,
+    pub v: Vector,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion {
+        w: 1.0,
+        v: Vector {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    };
+
+    pub fn new(w: f64, v: Vector) -> Self {
+        Quaternion { w, v }
+    }
+
+    // Rotates anticlockwise about axis, looking down it towards the origin.
+    pub fn from_axis_angle(axis: Vector, degrees: f64) -> Self {
+        let (sin, cos) = (0.5 * degrees.to_radians()).sin_cos();
+        Quaternion::new(cos, axis.normalize() * sin)
+    }
+
+    // The rotation made by a rotation matrix, found from whichever of the diagonal
+    // terms is largest so that nothing is divided by a small number.
+    pub fn from_matrix(matrix: Matrix3) -> Self {
+        let m = matrix.rows;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = match trace > 0.0 {
+            true => {
+                let s = 2.0 * (trace + 1.0).sqrt();
+                Quaternion::new(
+                    0.25 * s,
+                    Vector::new(m[2][1] - m[1][2], m[0][2] - m[2][0], m[1][0] - m[0][1]) / s,
+                )
+            }
+            false => {
+                let i = match (m[0][0] >= m[1][1], m[0][0] >= m[2][2], m[1][1] >= m[2][2]) {
+                    (true, true, _) => 0,
+                    (false, _, true) => 1,
+                    _ => 2,
+                };
+                let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+                let s = 2.0 * (1.0 + m[i][i] - m[j][j] - m[k][k]).sqrt();
+                let mut v = Vector::new(0.0, 0.0, 0.0);
+                v[i] = 0.25 * s;
+                v[j] = (m[j][i] + m[i][j]) / s;
+                v[k] = (m[k][i] + m[i][k]) / s;
+                Quaternion::new((m[k][j] - m[j][k]) / s, v)
+            }
+        };
+        q.normalize()
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.w * other.w + dot(self.v, other.v)
+    }
+
+    pub fn normalize(self) -> Self {
+        let length = self.dot(self).sqrt();
+        Quaternion::new(self.w / length, self.v / length)
+    }
+
+    pub fn conjugate(self) -> Self {
+        Quaternion::new(self.w, -self.v)
+    }
+
+    // Whether every component is within epsilon of other's. A rotation and its
+    // negation are different quaternions for the same rotation, and do not compare as
+    // equal.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        (self.w - other.w).abs() <= epsilon && self.v.approx_eq(&other.v, epsilon)
+    }
+
+    pub fn rotate(self, vector: Vector) -> Vector {
+        // v' = v + 2w(q x v) + 2q x (q x v), for a unit quaternion.
+        let t = self.v.cross(vector) * 2.0;
+        vector + t * self.w + self.v.cross(t)
+    }
+
+    pub fn to_matrix(self) -> Matrix3 {
+        Matrix3::from_columns(
+            self.rotate(Vector::new(1.0, 0.0, 0.0)),
+            self.rotate(Vector::new(0.0, 1.0, 0.0)),
+            self.rotate(Vector::new(0.0, 0.0, 1.0)),
+        )
+    }
+
+    // Spherical linear interpolation, turning at a constant rate from self at t = 0
+    // to other at t = 1 the short way round.
+    pub fn slerp(self, other: Self, t: f64) -> Self {
+        let mut other = other;
+        let mut cos_theta = self.dot(other);
+        if cos_theta < 0.0 {
+            other = Quaternion::new(-other.w, -other.v);
+            cos_theta = -cos_theta;
+        }
+
+        // Nearly the same rotation, where the sines below would lose precision.
+        if cos_theta > 0.9995 {
+            return Quaternion::new(
+                self.w + (other.w - self.w) * t,
+                self.v + (other.v - self.v) * t,
+            )
+            .normalize();
+        }
+
+        let theta = cos_theta.acos();
+        let a = ((1.0 - t) * theta).sin() / theta.sin();
+        let b = (t * theta).sin() / theta.sin();
+        Quaternion::new(self.w * a + other.w * b, self.v * a + other.v * b)
+    }
+}
+
+// Within EPSILON in every component, as for vectors.
+impl PartialEq for Quaternion {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, EPSILON)
+    }
+}
+
+// The Hamilton product: self * other rotates by other and then by self.
+impl Mul for Quaternion {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Quaternion::new(
+            self.w * rhs.w - dot(self.v, rhs.v),
+            rhs.v * self.w + self.v * rhs.w + self.v.cross(rhs.v),
+        )
+    }
+}
+
+// An orthonormal basis with w along a given direction, for working in a local frame
+// such as the one around a surface normal.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Onb {
+    pub u: Vector,
+    pub v: Vector,
+    pub w: Vector,
+}
+
+impl Onb {
+    // Uses the branchless construction of Duff et al., which has no direction where
+    // it breaks down.
+    pub fn new(direction: Vector) -> Self {
+        let w = direction.normalize();
+        let sign = 1.0_f64.copysign(w.z);
+        let a = -1.0 / (sign + w.z);
+        let b = w.x * w.y * a;
+        let u = Vector::new(1.0 + sign * w.x * w.x * a, sign * b, -sign * w.x);
+        let v = Vector::new(b, sign + w.y * w.y * a, -w.y);
+        Onb { u, v, w }
+    }
+
+    // From coordinates in this basis to world space.
+    pub fn to_world(&self, local: Vector) -> Vector {
+        self.u * local.x + self.v * local.y + self.w * local.z
+    }
+
+    // From world space to coordinates in this basis.
+    pub fn to_local(&self, vector: Vector) -> Vector {
+        Vector::new(
+            dot(vector, self.u),
+            dot(vector, self.v),
+            dot(vector, self.w),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiny_scales_are_invertible() {
+        let linear = Matrix3::new([[1e-6, 0.0, 0.0], [0.0, 1e-6, 0.0], [0.0, 0.0, 1e-6]]);
+        let inverse = linear
+            .inverse()
+            .expect("a uniform scale of 1e-6 is invertible");
+        assert!((inverse.rows[1][1] - 1e6).abs() < 1e-3);
+
+        let matrix = Matrix4::from_linear(linear, Vector::new(5.0, -2.0, 3.0));
+        let inverse = matrix
+            .inverse()
+            .expect("a uniform scale of 1e-6 is invertible");
+        assert!((inverse * matrix).approx_eq(&Matrix4::IDENTITY, 1e-9));
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        let flat = Matrix3::new([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 0.0]]);
+        assert!(flat.inverse().is_none());
+        assert!(
+            Matrix4::from_linear(flat, Vector::new(1.0, 0.0, 0.0))
+                .inverse()
+                .is_none()
+        );
+
+        let huge = Matrix3::new([[1e9, 2e9, 3e9], [2e9, 4e9, 6e9], [0.0, 1e9, 0.0]]);
+        assert!(huge.inverse().is_none());
+    }
+
+    #[test]
+    fn inverse_undoes_a_general_matrix() {
+        let matrix = Matrix4::new([
+            [2.0, 1.0, 0.0, 4.0],
+            [0.0, 3.0, 1.0, -1.0],
+            [1.0, 0.0, 5.0, 2.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let product = matrix.inverse().unwrap() * matrix;
+        assert!(product.approx_eq(&Matrix4::IDENTITY, 1e-9));
+    }
+
+    #[test]
+    fn equality_allows_for_rounding() {
+        let a = Vector::new(0.1, 0.2, 0.3);
+        let b = a + Vector::new(1e-12, 0.0, 0.0);
+        assert_eq!(a, b);
+        assert_ne!(a, a + Vector::new(0.0, 1e-6, 0.0));
+        assert!(!a.approx_eq(&b, 1e-13));
+
+        let rotation = Quaternion::from_axis_angle(Vector::new(1.0, 2.0, 3.0), 40.0);
+        assert_eq!(
+            rotation.to_matrix() * rotation.to_matrix().transpose(),
+            Matrix3::IDENTITY
+        );
+        assert_eq!(rotation * rotation.conjugate(), Quaternion::IDENTITY);
+        assert_ne!(
+            Matrix4::from_linear(Matrix3::IDENTITY * 2.0, Vector::new(0.0, 0.0, 0.0)),
+            Matrix4::IDENTITY
+        );
+    }
+
+    #[test]
+    fn rotation_matrices_give_back_their_quaternion() {
+        for (axis, degrees) in [
+            (Vector::new(0.0, 1.0, 0.0), 30.0),
+            (Vector::new(1.0, -2.0, 0.5), 170.0),
+            (Vector::new(0.0, 0.0, 1.0), 180.0),
+            (Vector::new(1.0, 0.0, 0.0), 180.0),
+            (Vector::new(-1.0, 3.0, 2.0), 300.0),
+        ] {
+            let rotation = Quaternion::from_axis_angle(axis, degrees);
+            let found = Quaternion::from_matrix(rotation.to_matrix());
+            assert!(
+                found.to_matrix().approx_eq(&rotation.to_matrix(), 1e-12),
+                "{axis:?} by {degrees}"
+            );
+        }
+    }
+
+    #[test]
+    fn slerp_halfway_is_half_the_angle() {
+        let axis = Vector::new(0.0, 1.0, 0.0);
+        let start = Quaternion::IDENTITY;
+        let end = Quaternion::from_axis_angle(axis, 90.0);
+        let halfway = start.slerp(end, 0.5);
+        assert!(halfway.approx_eq(&Quaternion::from_axis_angle(axis, 45.0), 1e-12));
+        assert!(start.slerp(end, 1.0).approx_eq(&end, 1e-12));
+        assert!(
+            halfway
+                .to_matrix()
+                .approx_eq(&Quaternion::from_axis_angle(axis, 45.0).to_matrix(), 1e-12)
+        );
+    }
+}
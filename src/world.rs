@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use crate::{
+    Colour,
+    environment::{ConstantEnvironment, Environment},
+    flat_bvh::FlatBvh,
+    hittable::{HitRecord, Hittable},
+    hittable_list::HittableList,
+    interval::Interval,
+    ray::Ray,
+    rng::random,
+    vector::{Point, Vector},
+};
+
+// Everything the camera needs to render: the surfaces to intersect, the emissive
+// ones among them, which are sampled directly at every diffuse bounce, the media
+// light passes through, and the environment seen by rays that escape the scene.
+pub struct World {
+    pub objects: Arc<Box<dyn Hittable>>,
+    pub lights: HittableList,
+    pub media: HittableList,
+    pub environment: Arc<Box<dyn Environment>>,
+}
+
+impl World {
+    // Builds a BVH over the surfaces and collects their lights. Unbounded objects, such
+    // as planes, cannot go in the BVH and are tested alongside it. Media are kept
+    // apart, so that shadow rays can pass through them. The environment is a dim blue
+    // grey until one is set.
+    pub fn new(objects: HittableList) -> Self {
+        let lights = objects.lights();
+        let (media, surfaces): (Vec<_>, Vec<_>) = objects
+            .objects()
+            .iter()
+            .cloned()
+            .partition(|object| object.is_medium());
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = surfaces
+            .into_iter()
+            .partition(|object| object.bounding_box().is_bounded());
+
+        let mut bvh_objects = HittableList::new();
+        for object in bounded {
+            bvh_objects.add(object);
+        }
+        let bvh: Box<dyn Hittable> = Box::new(FlatBvh::new(bvh_objects));
+        let objects: Box<dyn Hittable> = match unbounded.is_empty() {
+            true => bvh,
+            false => {
+                let mut list = HittableList::new();
+                list.add(Arc::new(bvh));
+                for object in unbounded {
+                    list.add(object);
+                }
+                Box::new(list)
+            }
+        };
+
+        let mut media_list = HittableList::new();
+        for medium in media {
+            media_list.add(medium);
+        }
+
+        World {
+            objects: Arc::new(objects),
+            lights,
+            media: media_list,
+            environment: Arc::new(Box::new(ConstantEnvironment::new(
+                0.25 * Colour::new(111.0 / 255.0, 144.0 / 255.0, 168.0 / 255.0),
+            ))),
+        }
+    }
+
+    pub fn with_environment(mut self, environment: Arc<Box<dyn Environment>>) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    // The first thing the ray meets within ray_t: a surface, or a point where it
+    // scatters in a medium before reaching one.
+    pub fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let surface = self.objects.hit(ray, ray_t);
+        let before_surface = match &surface {
+            Some(rec) => Interval::new(ray_t.min, rec.t),
+            None => ray_t,
+        };
+        self.media.hit(ray, before_surface).or(surface)
+    }
+
+    // The fraction of the light along the ray within ray_t that makes it through the
+    // media. Surfaces in the way are not taken into account.
+    pub fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        self.media.transmittance(ray, ray_t)
+    }
+
+    // Whether there is anything to sample directly: emissive objects, or an
+    // environment that can be sampled.
+    pub fn has_lights(&self) -> bool {
+        !self.lights.is_empty() || self.environment.is_sampled()
+    }
+
+    // Picks a direction from origin towards the lights or the environment. When there
+    // are both, each is chosen half the time. None if the light picked cannot be
+    // sampled, which its zero pdf_value already accounts for.
+    pub fn sample_light(&self, origin: Point) -> Option<Vector> {
+        let direction = match self.light_selection_probability() {
+            p if p >= 1.0 => self.lights.random(origin),
+            p if p <= 0.0 => self.environment.random(),
+            p => match random::<f64>() < p {
+                true => self.lights.random(origin),
+                false => self.environment.random(),
+            },
+        };
+        (direction.magnitude2() > 0.0).then_some(direction)
+    }
+
+    // The solid angle density of sample_light producing direction.
+    pub fn light_pdf(&self, origin: Point, direction: Vector) -> f64 {
+        let p = self.light_selection_probability();
+        let mut pdf = 0.0;
+        if p > 0.0 {
+            pdf += p * self.lights.pdf_value(origin, direction);
+        }
+        if p < 1.0 {
+            pdf += (1.0 - p) * self.environment.pdf_value(direction);
+        }
+        pdf
+    }
+
+    // How often sample_light chooses the emissive objects over the environment.
+    fn light_selection_probability(&self) -> f64 {
+        match (self.lights.is_empty(), self.environment.is_sampled()) {
+            (false, true) => 0.5,
+            (false, false) => 1.0,
+            (true, _) => 0.0,
+        }
+    }
+}